@@ -0,0 +1,75 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// Record the git commit, the rustc version and the enabled cargo features
+// for the GetBuildInfo method.
+
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::process::Command;
+
+/// The first line of the output of a successful command, if any.
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout)
+        .ok()
+        .and_then(|s| s.lines().next().map(|l| l.trim().to_owned()))
+        .filter(|s| !s.is_empty())
+}
+
+/// The files whose change means that HEAD may name a different commit:
+/// HEAD itself, the ref it points to, if any, and the packed refs. Only
+/// those which exist are returned, as cargo reruns the build script on
+/// every build if a file it watches is missing.
+fn git_head_files() -> Vec<String> {
+    let mut files = vec![".git/HEAD".to_owned()];
+
+    let mut head = String::new();
+    if File::open(".git/HEAD")
+           .and_then(|mut f| f.read_to_string(&mut head))
+           .is_ok() {
+        let head = head.trim();
+        if head.starts_with("ref: ") {
+            files.push(format!(".git/{}", &head["ref: ".len()..]));
+        }
+    }
+
+    files.push(".git/packed-refs".to_owned());
+    files
+        .into_iter()
+        .filter(|f| Path::new(f).exists())
+        .collect()
+}
+
+const FEATURE_PREFIX: &'static str = "CARGO_FEATURE_";
+
+/// The names of the enabled cargo features, which cargo passes to the
+/// build script as CARGO_FEATURE_<name> variables, separated by commas.
+fn enabled_features() -> String {
+    let mut features = env::vars()
+        .filter(|&(ref var, _)| var.starts_with(FEATURE_PREFIX))
+        .map(|(var, _)| var[FEATURE_PREFIX.len()..].to_lowercase().replace('_', "-"))
+        .collect::<Vec<String>>();
+    features.sort();
+    features.join(",")
+}
+
+fn main() {
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".into());
+
+    let commit = command_output("git", &["rev-parse", "HEAD"]).unwrap_or_else(|| "unknown".into());
+    let rustc_version = command_output(&rustc, &["--version"]).unwrap_or_else(|| "unknown".into());
+
+    println!("cargo:rustc-env=STRATIS_GIT_COMMIT={}", commit);
+    println!("cargo:rustc-env=STRATIS_RUSTC_VERSION={}", rustc_version);
+    println!("cargo:rustc-env=STRATIS_FEATURES={}", enabled_features());
+    for file in git_head_files() {
+        println!("cargo:rerun-if-changed={}", file);
+    }
+}
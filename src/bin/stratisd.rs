@@ -57,6 +57,11 @@ fn run() -> StratisResult<()> {
         .arg(Arg::with_name("sim")
                  .long("sim")
                  .help("Use simulator engine"))
+        .arg(Arg::with_name("replace")
+                 .long("replace")
+                 .help("Take over the D-Bus name from a running or stuck daemon. \
+                        By default, the name is not taken over, and startup fails \
+                        if it is already owned."))
         .get_matches();
 
     let mut builder = LogBuilder::new();
@@ -83,7 +88,8 @@ fn run() -> StratisResult<()> {
         }
     };
 
-    let (dbus_conn, mut tree, dbus_context) = libstratis::dbus_api::connect(Rc::clone(&engine))?;
+    let (dbus_conn, mut tree, dbus_context) =
+        libstratis::dbus_api::connect(Rc::clone(&engine), matches.is_present("replace"))?;
 
     // Get a list of fds to poll for
     let mut fds: Vec<_> = dbus_conn
@@ -4,9 +4,11 @@
 
 use std::borrow::Cow;
 use std::fmt::Display;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::vec::Vec;
 use std::rc::Rc;
+use std::time::UNIX_EPOCH;
 use std::cell::RefCell;
 
 use dbus;
@@ -28,18 +30,24 @@ use dbus::tree::PropInfo;
 use dbus::tree::Tree;
 use dbus::ConnectionItem;
 
-use engine::{Engine, Redundancy};
-use stratis::VERSION;
+use devicemapper::Sectors;
+
+use engine::{Engine, EngineResult, Redundancy};
+use stratis::{GIT_COMMIT, RUSTC_VERSION, VERSION, enabled_features};
 
 use super::filesystem::create_dbus_filesystem;
 use super::pool::create_dbus_pool;
+use super::pool::release_dbus_pool_paths;
+use super::pool::reserve_dbus_pool_path;
 use super::types::{DeferredAction, DbusContext, DbusErrorEnum, TData};
 use super::util::STRATIS_BASE_PATH;
 use super::util::STRATIS_BASE_SERVICE;
+use super::util::check_expected_uuid;
 use super::util::code_to_message_items;
 use super::util::default_object_path;
 use super::util::engine_to_dbus_err;
 use super::util::get_next_arg;
+use super::util::name_reply_to_result;
 use super::util::ok_message_items;
 use super::util::tuple_to_option;
 
@@ -54,19 +62,130 @@ fn create_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
 
     let blockdevs = devs.map(|x| Path::new(x)).collect::<Vec<&Path>>();
 
+    let redundancy = m.tree
+        .get_data()
+        .redundancy_or_default(tuple_to_option(redundancy));
+
+    create_pool_reply(m, name, Ok(Some(redundancy)), force, &blockdevs, false, false)
+}
+
+fn create_pool_with_options(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let mut iter = message.iter_init();
+
+    let name: &str = get_next_arg(&mut iter, 0)?;
+    let redundancy: (bool, u16) = get_next_arg(&mut iter, 1)?;
+    let force: bool = get_next_arg(&mut iter, 2)?;
+    let devs: Array<&str, _> = get_next_arg(&mut iter, 3)?;
+    let dedup: bool = get_next_arg(&mut iter, 4)?;
+    let require_uniform_devices: bool = get_next_arg(&mut iter, 5)?;
+
+    let blockdevs = devs.map(|x| Path::new(x)).collect::<Vec<&Path>>();
+
+    let redundancy = m.tree
+        .get_data()
+        .redundancy_or_default(tuple_to_option(redundancy));
+
+    create_pool_reply(m,
+                      name,
+                      Ok(Some(redundancy)),
+                      force,
+                      &blockdevs,
+                      dedup,
+                      require_uniform_devices)
+}
+
+fn create_pool_by_redundancy_name(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let mut iter = message.iter_init();
+
+    let name: &str = get_next_arg(&mut iter, 0)?;
+    let redundancy: &str = get_next_arg(&mut iter, 1)?;
+    let force: bool = get_next_arg(&mut iter, 2)?;
+    let devs: Array<&str, _> = get_next_arg(&mut iter, 3)?;
+    let dedup: bool = get_next_arg(&mut iter, 4)?;
+    let require_uniform_devices: bool = get_next_arg(&mut iter, 5)?;
+
+    let blockdevs = devs.map(|x| Path::new(x)).collect::<Vec<&Path>>();
+
+    create_pool_reply(m,
+                      name,
+                      Redundancy::from_str(redundancy).map(|r| Some(u16::from(r))),
+                      force,
+                      &blockdevs,
+                      dedup,
+                      require_uniform_devices)
+}
+
+/// Create a pool and its D-Bus object, using the path reserved for name
+/// if there is one. If the redundancy code was not obtained or the pool can
+/// not be created, the reserved path is released.
+fn create_pool_object(dbus_context: &DbusContext,
+                      parent: dbus::Path<'static>,
+                      name: &str,
+                      blockdevs: &[&Path],
+                      redundancy: EngineResult<Option<u16>>,
+                      force: bool,
+                      dedup: bool,
+                      require_uniform_devices: bool)
+                      -> EngineResult<(dbus::Path<'static>, Vec<PathBuf>)> {
+    let reserved = dbus_context
+        .reserved_pools
+        .borrow_mut()
+        .remove(name)
+        .map(|(path, _)| path);
+    let result = redundancy.and_then(|redundancy| {
+        dbus_context
+            .engine
+            .borrow_mut()
+            .create_pool(name,
+                         blockdevs,
+                         redundancy,
+                         force,
+                         dedup,
+                         require_uniform_devices)
+    });
+    match result {
+        Ok((uuid, devnodes)) => {
+            Ok((create_dbus_pool(dbus_context, parent, uuid, reserved), devnodes))
+        }
+        Err(err) => {
+            if let Some(path) = reserved {
+                dbus_context.actions.borrow_mut().push_remove(path);
+            }
+            Err(err)
+        }
+    }
+}
+
+/// Create a pool with the given redundancy code, if the code was
+/// obtained successfully, and construct the reply to a pool creation
+/// method.
+fn create_pool_reply(m: &MethodInfo<MTFn<TData>, TData>,
+                     name: &str,
+                     redundancy: EngineResult<Option<u16>>,
+                     force: bool,
+                     blockdevs: &[&Path],
+                     dedup: bool,
+                     require_uniform_devices: bool)
+                     -> MethodResult {
+    let message: &Message = m.msg;
+
     let object_path = m.path.get_name();
     let dbus_context = m.tree.get_data();
-    let result = dbus_context
-        .engine
-        .borrow_mut()
-        .create_pool(name, &blockdevs, tuple_to_option(redundancy), force);
+    let result = create_pool_object(dbus_context,
+                                    object_path.clone(),
+                                    name,
+                                    blockdevs,
+                                    redundancy,
+                                    force,
+                                    dedup,
+                                    require_uniform_devices);
 
     let return_message = message.method_return();
 
     let msg = match result {
-        Ok((uuid, devnodes)) => {
-            let pool_object_path: dbus::Path =
-                create_dbus_pool(dbus_context, object_path.clone(), uuid);
+        Ok((pool_object_path, devnodes)) => {
             let paths = devnodes
                 .iter()
                 .map(|d| {
@@ -93,12 +212,61 @@ fn create_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
     Ok(vec![msg])
 }
 
+fn reserve_pool_path(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let mut iter = message.iter_init();
+
+    let name: &str = get_next_arg(&mut iter, 0)?;
+
+    let dbus_context = m.tree.get_data();
+    let return_message = message.method_return();
+
+    if dbus_context
+           .engine
+           .borrow()
+           .pools()
+           .iter()
+           .any(|p| p.name() == name) {
+        let error_message = format!("a pool named {} already exists", name);
+        let (rc, rs) = code_to_message_items(DbusErrorEnum::ALREADY_EXISTS, error_message);
+        return Ok(vec![return_message.append3(MessageItem::ObjectPath(default_object_path()),
+                                              rc,
+                                              rs)]);
+    }
+
+    let sender = message.sender().map(|s| s.to_string());
+    let pool_object_path =
+        reserve_dbus_pool_path(dbus_context, name, sender.as_ref().map(|s| s.as_str()));
+    let (rc, rs) = ok_message_items();
+    Ok(vec![return_message.append3(MessageItem::ObjectPath(pool_object_path), rc, rs)])
+}
+
 fn destroy_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let mut iter = message.iter_init();
+
+    let object_path: dbus::Path<'static> = get_next_arg(&mut iter, 0)?;
 
+    destroy_pool_reply(m, object_path, None)
+}
+
+fn destroy_pool_with_expected_uuid(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
     let message: &Message = m.msg;
     let mut iter = message.iter_init();
 
     let object_path: dbus::Path<'static> = get_next_arg(&mut iter, 0)?;
+    let expected_uuid: (bool, &str) = get_next_arg(&mut iter, 1)?;
+
+    destroy_pool_reply(m, object_path, tuple_to_option(expected_uuid))
+}
+
+/// Destroy the pool at object_path, if it has the expected UUID, and
+/// construct the reply to a pool destruction method.
+fn destroy_pool_reply(m: &MethodInfo<MTFn<TData>, TData>,
+                      object_path: dbus::Path<'static>,
+                      expected_uuid: Option<&str>)
+                      -> MethodResult {
+    let message: &Message = m.msg;
 
     let dbus_context = m.tree.get_data();
 
@@ -113,6 +281,11 @@ fn destroy_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
         }
     };
 
+    if let Err((rc, rs)) = check_expected_uuid(expected_uuid, &pool_uuid) {
+        let (rc, rs) = code_to_message_items(rc, rs);
+        return Ok(vec![return_message.append3(default_return, rc, rs)]);
+    }
+
     let msg = match dbus_context
               .engine
               .borrow_mut()
@@ -134,7 +307,29 @@ fn destroy_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
     Ok(vec![msg])
 }
 
-fn get_list_items<T, I>(i: &mut IterAppend, iter: I) -> Result<(), MethodErr>
+fn list_pools(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let mut iter = message.iter_init();
+
+    let include_hidden: bool = get_next_arg(&mut iter, 0)?;
+
+    let dbus_context = m.tree.get_data();
+    let names = dbus_context
+        .engine
+        .borrow()
+        .list_pools(include_hidden)
+        .iter()
+        .map(|p| MessageItem::Str(p.name().into()))
+        .collect();
+
+    let (rc, rs) = ok_message_items();
+    Ok(vec![message
+                .method_return()
+                .append3(MessageItem::Array(names, "s".into()), rc, rs)])
+}
+
+/// The name and code of each item, as a D-Bus array of structs.
+fn list_items<T, I>(iter: I) -> MessageItem
     where T: Display + Into<u16>,
           I: Iterator<Item = T>
 {
@@ -143,7 +338,14 @@ fn get_list_items<T, I>(i: &mut IterAppend, iter: I) -> Result<(), MethodErr>
                                                         MessageItem::UInt16(item.into())])
                            })
         .collect::<Vec<MessageItem>>();
-    i.append(MessageItem::Array(msg_vec, Cow::Borrowed("(sq)")));
+    MessageItem::Array(msg_vec, Cow::Borrowed("(sq)"))
+}
+
+fn get_list_items<T, I>(i: &mut IterAppend, iter: I) -> Result<(), MethodErr>
+    where T: Display + Into<u16>,
+          I: Iterator<Item = T>
+{
+    i.append(list_items(iter));
     Ok(())
 }
 
@@ -165,6 +367,25 @@ fn get_version(i: &mut IterAppend, _p: &PropInfo<MTFn<TData>, TData>) -> Result<
     Ok(())
 }
 
+/// The version, git commit, enabled features, and rustc version of this
+/// build, as a D-Bus struct.
+fn build_info() -> MessageItem {
+    let features = enabled_features()
+        .iter()
+        .map(|f| MessageItem::Str((*f).into()))
+        .collect();
+    MessageItem::Struct(vec![MessageItem::Str(VERSION.into()),
+                             MessageItem::Str(GIT_COMMIT.into()),
+                             MessageItem::Array(features, "s".into()),
+                             MessageItem::Str(RUSTC_VERSION.into())])
+}
+
+fn get_build_info(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let (rc, rs) = ok_message_items();
+    Ok(vec![message.method_return().append3(build_info(), rc, rs)])
+}
+
 fn configure_simulator(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
     let message = m.msg;
     let mut iter = message.iter_init();
@@ -193,6 +414,258 @@ fn configure_simulator(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
     Ok(vec![msg])
 }
 
+fn get_supported_fs_types(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+
+    let dbus_context = m.tree.get_data();
+    let fs_types = dbus_context
+        .engine
+        .borrow()
+        .supported_fs_types()
+        .iter()
+        .map(|t| MessageItem::Str((*t).into()))
+        .collect();
+
+    let (rc, rs) = ok_message_items();
+    Ok(vec![message
+                .method_return()
+                .append3(MessageItem::Array(fs_types, "s".into()), rc, rs)])
+}
+
+fn get_engine_kind(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+
+    let dbus_context = m.tree.get_data();
+    let kind = format!("{}", dbus_context.engine.borrow().kind());
+
+    let (rc, rs) = ok_message_items();
+    Ok(vec![message
+                .method_return()
+                .append3(MessageItem::Str(kind), rc, rs)])
+}
+
+fn get_default_redundancy(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+
+    let redundancy = m.tree.get_data().default_redundancy.get();
+
+    let (rc, rs) = ok_message_items();
+    Ok(vec![message
+                .method_return()
+                .append3(MessageItem::UInt16(redundancy), rc, rs)])
+}
+
+fn set_default_redundancy(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let mut iter = message.iter_init();
+
+    let redundancy: u16 = get_next_arg(&mut iter, 0)?;
+
+    let dbus_context = m.tree.get_data();
+    let return_message = message.method_return();
+
+    let msg = if Redundancy::iter_variants().nth(redundancy as usize).is_some() {
+        let action = dbus_context.default_redundancy.get() != redundancy;
+        dbus_context.default_redundancy.set(redundancy);
+        let (rc, rs) = ok_message_items();
+        return_message.append3(MessageItem::Bool(action), rc, rs)
+    } else {
+        let message = format!("code {} does not correspond to any redundancy", redundancy);
+        let (rc, rs) = code_to_message_items(DbusErrorEnum::ERROR, message);
+        return_message.append3(MessageItem::Bool(false), rc, rs)
+    };
+    Ok(vec![msg])
+}
+
+fn get_data_dir(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+
+    let dbus_context = m.tree.get_data();
+    let data_dir = format!("{}", dbus_context.engine.borrow().data_dir().display());
+
+    let (rc, rs) = ok_message_items();
+    Ok(vec![message
+                .method_return()
+                .append3(MessageItem::Str(data_dir), rc, rs)])
+}
+
+fn list_recently_destroyed(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+
+    let dbus_context = m.tree.get_data();
+    let destroyed = dbus_context
+        .engine
+        .borrow()
+        .recently_destroyed()
+        .iter()
+        .map(|info| {
+            let destroyed = info.destroyed
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            MessageItem::Struct(vec![MessageItem::Str(info.name.clone()),
+                                     MessageItem::Str(format!("{}", info.uuid.simple())),
+                                     MessageItem::UInt64(destroyed)])
+        })
+        .collect();
+
+    let (rc, rs) = ok_message_items();
+    Ok(vec![message
+                .method_return()
+                .append3(MessageItem::Array(destroyed, "(sst)".into()), rc, rs)])
+}
+
+fn get_filesystems_affected_by_device(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let mut iter = message.iter_init();
+
+    let device: &str = get_next_arg(&mut iter, 0)?;
+
+    let dbus_context = m.tree.get_data();
+    let return_message = message.method_return();
+
+    let msg = match dbus_context
+              .engine
+              .borrow()
+              .filesystems_affected_by_device(Path::new(device)) {
+        Ok(names) => {
+            let names = names.into_iter().map(MessageItem::Str).collect();
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Array(names, "s".into()), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(MessageItem::Array(vec![], "s".into()), rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+/// Return, as a (bs) struct, whether the device appears to be empty, and
+/// the kind of data the engine's device_signature() found on it, e.g.,
+/// "stratis" or "ext4". The string is empty if the device is empty, or if
+/// it could not be examined.
+fn check_device_empty(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let mut iter = message.iter_init();
+
+    let device: &str = get_next_arg(&mut iter, 0)?;
+
+    let dbus_context = m.tree.get_data();
+    let return_message = message.method_return();
+
+    let msg = match dbus_context
+              .engine
+              .borrow()
+              .device_signature(Path::new(device)) {
+        Ok(signature) => {
+            let (rc, rs) = ok_message_items();
+            let empty = signature.is_none();
+            let result = MessageItem::Struct(vec![MessageItem::Bool(empty),
+                                                  MessageItem::Str(signature.unwrap_or_default())]);
+            return_message.append3(result, rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            let result = MessageItem::Struct(vec![MessageItem::Bool(false),
+                                                  MessageItem::Str("".into())]);
+            return_message.append3(result, rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+fn get_metrics_prometheus(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+
+    let dbus_context = m.tree.get_data();
+    let metrics = dbus_context.engine.borrow().metrics_prometheus();
+
+    let (rc, rs) = ok_message_items();
+    Ok(vec![message
+                .method_return()
+                .append3(MessageItem::Str(metrics), rc, rs)])
+}
+
+fn min_device_size_for(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let mut iter = message.iter_init();
+
+    let volume_size: u64 = get_next_arg(&mut iter, 0)?;
+    let redundancy: u16 = get_next_arg(&mut iter, 1)?;
+    let num_devices: u32 = get_next_arg(&mut iter, 2)?;
+
+    let dbus_context = m.tree.get_data();
+    let return_message = message.method_return();
+    let default_return = MessageItem::UInt64(0);
+
+    let redundancy = match Redundancy::iter_variants().nth(redundancy as usize) {
+        Some(r) => r,
+        None => {
+            let message = format!("code {} does not correspond to any redundancy", redundancy);
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::ERROR, message);
+            return Ok(vec![return_message.append3(default_return, rc, rs)]);
+        }
+    };
+
+    let msg = match dbus_context
+              .engine
+              .borrow()
+              .min_device_size_for(Sectors(volume_size), &redundancy, num_devices as usize) {
+        Ok(size) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::UInt64(*size), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+fn get_redundancy_usable_factor(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let mut iter = message.iter_init();
+
+    let redundancy: u16 = get_next_arg(&mut iter, 0)?;
+    let num_devices: u32 = get_next_arg(&mut iter, 1)?;
+
+    let return_message = message.method_return();
+
+    let msg = match Redundancy::iter_variants().nth(redundancy as usize) {
+        Some(r) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Double(r.usable_factor(num_devices as usize)),
+                                   rc,
+                                   rs)
+        }
+        None => {
+            let message = format!("code {} does not correspond to any redundancy", redundancy);
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::ERROR, message);
+            return_message.append3(MessageItem::Double(0.0), rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+fn get_usable_redundancy_levels(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let mut iter = message.iter_init();
+
+    let num_devices: u32 = get_next_arg(&mut iter, 0)?;
+
+    let levels = Redundancy::iter_variants().filter(|r| r.min_devices() <= num_devices as usize);
+
+    let (rc, rs) = ok_message_items();
+    Ok(vec![message
+                .method_return()
+                .append3(list_items(levels), rc, rs)])
+}
+
 fn get_base_tree<'a>(dbus_context: DbusContext) -> (Tree<MTFn<TData>, TData>, dbus::Path<'a>) {
 
     let f = Factory::new_fn();
@@ -208,17 +681,143 @@ fn get_base_tree<'a>(dbus_context: DbusContext) -> (Tree<MTFn<TData>, TData>, db
         .out_arg(("return_code", "q"))
         .out_arg(("return_string", "s"));
 
+    let create_pool_with_options_method =
+        f.method("CreatePoolWithOptions", (), create_pool_with_options)
+            .in_arg(("name", "s"))
+            .in_arg(("redundancy", "(bq)"))
+            .in_arg(("force", "b"))
+            .in_arg(("devices", "as"))
+            .in_arg(("dedup", "b"))
+            .in_arg(("require_uniform_devices", "b"))
+            .out_arg(("result", "(oas)"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let create_pool_by_redundancy_name_method =
+        f.method("CreatePoolByRedundancyName", (), create_pool_by_redundancy_name)
+            .in_arg(("name", "s"))
+            .in_arg(("redundancy", "s"))
+            .in_arg(("force", "b"))
+            .in_arg(("devices", "as"))
+            .in_arg(("dedup", "b"))
+            .in_arg(("require_uniform_devices", "b"))
+            .out_arg(("result", "(oas)"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let reserve_pool_path_method = f.method("ReservePoolPath", (), reserve_pool_path)
+        .in_arg(("name", "s"))
+        .out_arg(("pool", "o"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
     let destroy_pool_method = f.method("DestroyPool", (), destroy_pool)
         .in_arg(("pool", "o"))
         .out_arg(("action", "b"))
         .out_arg(("return_code", "q"))
         .out_arg(("return_string", "s"));
 
+    let destroy_pool_with_expected_uuid_method =
+        f.method("DestroyPoolWithExpectedUuid", (), destroy_pool_with_expected_uuid)
+            .in_arg(("pool", "o"))
+            .in_arg(("expected_uuid", "(bs)"))
+            .out_arg(("action", "b"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let list_pools_method = f.method("ListPools", (), list_pools)
+        .in_arg(("include_hidden", "b"))
+        .out_arg(("names", "as"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
     let configure_simulator_method = f.method("ConfigureSimulator", (), configure_simulator)
         .in_arg(("denominator", "u"))
         .out_arg(("return_code", "q"))
         .out_arg(("return_string", "s"));
 
+    let get_supported_fs_types_method =
+        f.method("GetSupportedFsTypes", (), get_supported_fs_types)
+            .out_arg(("fs_types", "as"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let get_build_info_method = f.method("GetBuildInfo", (), get_build_info)
+        .out_arg(("build_info", "(ssass)"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_default_redundancy_method =
+        f.method("GetDefaultRedundancy", (), get_default_redundancy)
+            .out_arg(("redundancy", "q"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let set_default_redundancy_method =
+        f.method("SetDefaultRedundancy", (), set_default_redundancy)
+            .in_arg(("redundancy", "q"))
+            .out_arg(("action", "b"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let get_engine_kind_method = f.method("GetEngineKind", (), get_engine_kind)
+        .out_arg(("kind", "s"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_data_dir_method = f.method("GetDataDir", (), get_data_dir)
+        .out_arg(("data_dir", "s"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let check_device_empty_method = f.method("CheckDeviceEmpty", (), check_device_empty)
+        .in_arg(("device", "s"))
+        .out_arg(("result", "(bs)"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_filesystems_affected_by_device_method =
+        f.method("GetFilesystemsAffectedByDevice", (), get_filesystems_affected_by_device)
+            .in_arg(("device", "s"))
+            .out_arg(("filesystems", "as"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let get_metrics_prometheus_method =
+        f.method("GetMetricsPrometheus", (), get_metrics_prometheus)
+            .out_arg(("metrics", "s"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let list_recently_destroyed_method =
+        f.method("ListRecentlyDestroyed", (), list_recently_destroyed)
+            .out_arg(("pools", "a(sst)"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let get_redundancy_usable_factor_method =
+        f.method("GetRedundancyUsableFactor", (), get_redundancy_usable_factor)
+            .in_arg(("redundancy", "q"))
+            .in_arg(("num_devices", "u"))
+            .out_arg(("usable_factor", "d"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let get_usable_redundancy_levels_method =
+        f.method("GetUsableRedundancyLevels", (), get_usable_redundancy_levels)
+            .in_arg(("num_devices", "u"))
+            .out_arg(("levels", "a(sq)"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let min_device_size_for_method = f.method("MinDeviceSizeFor", (), min_device_size_for)
+        .in_arg(("volume_size", "t"))
+        .in_arg(("redundancy", "q"))
+        .in_arg(("num_devices", "u"))
+        .out_arg(("min_device_size", "t"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
     let redundancy_values_property =
         f.property::<Array<(&str, u16), &Iterator<Item = (&str, u16)>>, _>("RedundancyValues", ())
             .access(Access::Read)
@@ -243,8 +842,26 @@ fn get_base_tree<'a>(dbus_context: DbusContext) -> (Tree<MTFn<TData>, TData>, db
         .object_manager()
         .add(f.interface(interface_name, ())
                  .add_m(create_pool_method)
+                 .add_m(create_pool_with_options_method)
+                 .add_m(create_pool_by_redundancy_name_method)
+                 .add_m(reserve_pool_path_method)
                  .add_m(destroy_pool_method)
+                 .add_m(destroy_pool_with_expected_uuid_method)
+                 .add_m(list_pools_method)
                  .add_m(configure_simulator_method)
+                 .add_m(get_supported_fs_types_method)
+                 .add_m(get_redundancy_usable_factor_method)
+                 .add_m(get_usable_redundancy_levels_method)
+                 .add_m(min_device_size_for_method)
+                 .add_m(get_data_dir_method)
+                 .add_m(get_build_info_method)
+                 .add_m(get_default_redundancy_method)
+                 .add_m(get_engine_kind_method)
+                 .add_m(set_default_redundancy_method)
+                 .add_m(list_recently_destroyed_method)
+                 .add_m(get_metrics_prometheus_method)
+                 .add_m(get_filesystems_affected_by_device_method)
+                 .add_m(check_device_empty_method)
                  .add_p(error_values_property)
                  .add_p(redundancy_values_property)
                  .add_p(version_property));
@@ -253,8 +870,12 @@ fn get_base_tree<'a>(dbus_context: DbusContext) -> (Tree<MTFn<TData>, TData>, db
     (base_tree.add(obj_path), path)
 }
 
+/// Connect to the system bus and register the Stratis service name.
+/// If replace is true, take the name over from any current owner willing
+/// to release it.
 #[allow(type_complexity)]
-pub fn connect(engine: Rc<RefCell<Engine>>)
+pub fn connect(engine: Rc<RefCell<Engine>>,
+               replace: bool)
                -> Result<(Connection, Tree<MTFn<TData>, TData>, DbusContext), dbus::Error> {
     let c = Connection::get_private(BusType::System)?;
 
@@ -266,7 +887,7 @@ pub fn connect(engine: Rc<RefCell<Engine>>)
     // This should never panic as create_dbus_pool() and
     // create_dbus_filesystem() do not borrow the engine.
     for pool in local_engine.borrow().pools() {
-        let pool_path = create_dbus_pool(&dbus_context, object_path.clone(), *pool.uuid());
+        let pool_path = create_dbus_pool(&dbus_context, object_path.clone(), *pool.uuid(), None);
         for fs_uuid in pool.filesystems().iter().map(|f| *f.uuid()) {
             create_dbus_filesystem(&dbus_context, pool_path.clone(), fs_uuid);
         }
@@ -274,39 +895,324 @@ pub fn connect(engine: Rc<RefCell<Engine>>)
 
     tree.set_registered(&c, true)?;
 
-    c.register_name(STRATIS_BASE_SERVICE, NameFlag::ReplaceExisting as u32)?;
+    // Watch for clients disconnecting, so that their reserved paths can be
+    // released.
+    c.add_match(NAME_OWNER_CHANGED_MATCH)?;
+
+    // Allow replacement, so that a daemon started later with --replace can
+    // take over the name from this one.
+    let flags = NameFlag::AllowReplacement as u32 | NameFlag::DoNotQueue as u32;
+    let flags = if replace {
+        flags | NameFlag::ReplaceExisting as u32
+    } else {
+        flags
+    };
+    name_reply_to_result(c.register_name(STRATIS_BASE_SERVICE, flags)?, replace)?;
 
     Ok((c, tree, dbus_context))
 }
 
+/// Match rule for the bus signal sent when a client connects or disconnects.
+const NAME_OWNER_CHANGED_MATCH: &'static str = "type='signal',sender='org.freedesktop.DBus',\
+                                                 interface='org.freedesktop.DBus',\
+                                                 member='NameOwnerChanged'";
+
+/// If msg reports that a client has disconnected from the bus, return the
+/// client's unique name.
+fn disconnected_client(msg: &Message) -> Option<String> {
+    if msg.interface().map_or(true, |i| &*i != "org.freedesktop.DBus") ||
+       msg.member().map_or(true, |m| &*m != "NameOwnerChanged") {
+        return None;
+    }
+    match msg.get3::<&str, &str, &str>() {
+        (Some(name), Some(old_owner), Some(""))
+            if name == old_owner => Some(name.to_owned()),
+        _ => None,
+    }
+}
+
+/// Apply the queued actions to the tree and the connection. A failure to
+/// register one object path is logged and does not prevent the remaining
+/// actions from being applied.
+fn process_actions(c: &Connection,
+                   tree: &mut Tree<MTFn<TData>, TData>,
+                   dbus_context: &DbusContext) {
+    let mut b_actions = dbus_context.actions.borrow_mut();
+    for action in b_actions.drain() {
+        match action {
+            DeferredAction::Add(path) => {
+                if let Err(err) = c.register_object_path(path.get_name()) {
+                    error!("Failed to register object path {}: {:?}",
+                           path.get_name(),
+                           err);
+                }
+                tree.insert(path);
+            }
+            DeferredAction::Remove(path) => {
+                c.unregister_object_path(&path);
+                tree.remove(&path);
+            }
+        }
+    }
+}
+
 pub fn handle(c: &Connection,
               item: &ConnectionItem,
               tree: &mut Tree<MTFn<TData>, TData>,
               dbus_context: &DbusContext)
               -> Result<(), dbus::Error> {
-    if let ConnectionItem::MethodCall(ref msg) = *item {
-        if let Some(v) = tree.handle(msg) {
-            // Probably the wisest is to ignore any send errors here -
-            // maybe the remote has disconnected during our processing.
-            for m in v {
-                let _ = c.send(m);
+    match *item {
+        ConnectionItem::MethodCall(ref msg) => {
+            let replies = tree.handle(msg);
+
+            // Register any new object paths before replying, so that a client
+            // may use a returned object path as soon as it receives the reply.
+            process_actions(c, tree, dbus_context);
+
+            if let Some(v) = replies {
+                // Probably the wisest is to ignore any send errors here -
+                // maybe the remote has disconnected during our processing.
+                for m in v {
+                    let _ = c.send(m);
+                }
+            }
+        }
+        ConnectionItem::Signal(ref msg) => {
+            if let Some(client) = disconnected_client(msg) {
+                release_dbus_pool_paths(dbus_context, &client);
+                process_actions(c, tree, dbus_context);
             }
         }
+        _ => {}
+    }
 
-        let mut b_actions = dbus_context.actions.borrow_mut();
-        for action in b_actions.drain() {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::path::Path;
+    use std::rc::Rc;
+
+    use dbus::{Member, Message, MessageItem};
+    use dbus::tree::{MTFn, Tree};
+
+    use engine::{Engine, EngineError, ErrorEnum, Redundancy, SimEngine};
+
+    use super::super::pool::{add_blockdevs_signalled, create_dbus_pool,
+                             release_dbus_pool_paths, reserve_dbus_pool_path};
+    use super::super::types::{DbusContext, DeferredAction, TData};
+
+    use super::{build_info, create_pool_object, disconnected_client, get_base_tree};
+
+    /// Apply the queued actions to the tree, as handle() would.
+    fn apply_actions(tree: &mut Tree<MTFn<TData>, TData>, dbus_context: &DbusContext) {
+        for action in dbus_context.actions.borrow_mut().drain() {
             match action {
-                DeferredAction::Add(path) => {
-                    c.register_object_path(path.get_name())?;
-                    tree.insert(path);
-                }
+                DeferredAction::Add(path) => tree.insert(path),
                 DeferredAction::Remove(path) => {
-                    c.unregister_object_path(&path);
                     tree.remove(&path);
                 }
             }
         }
     }
 
-    Ok(())
+    #[test]
+    /// The build info reports the crate version first.
+    fn build_info_version() {
+        match build_info() {
+            MessageItem::Struct(items) => {
+                assert_eq!(items[0], MessageItem::Str(env!("CARGO_PKG_VERSION").into()))
+            }
+            _ => panic!("build info must be a struct"),
+        }
+    }
+
+    #[test]
+    /// A reserved path is a placeholder object in the tree until the pool
+    /// is created, when it is replaced by the pool object at the same path.
+    fn reserved_pool_path() {
+        let engine = Rc::new(RefCell::new(SimEngine::default()));
+        let (mut tree, base_path) = get_base_tree(DbusContext::new(engine.clone()));
+        let dbus_context = tree.get_data().clone();
+
+        let name = "name";
+        let reserved = reserve_dbus_pool_path(&dbus_context, name, None);
+        assert_eq!(reserve_dbus_pool_path(&dbus_context, name, None), reserved);
+        apply_actions(&mut tree, &dbus_context);
+        assert!(tree.get(&reserved).unwrap().get_data().is_none());
+
+        let (uuid, _) = engine
+            .borrow_mut()
+            .create_pool(name, &[Path::new("/s/d")], None, false, false, false)
+            .unwrap();
+        let taken = dbus_context
+            .reserved_pools
+            .borrow_mut()
+            .remove(name)
+            .map(|(path, _)| path);
+        let pool_path = create_dbus_pool(&dbus_context, base_path, uuid, taken);
+        apply_actions(&mut tree, &dbus_context);
+
+        assert_eq!(pool_path, reserved);
+        assert_eq!(tree.get(&reserved).unwrap().get_data().as_ref().unwrap().uuid,
+                   uuid);
+    }
+
+    #[test]
+    /// A reserved path is released if the pool can not be created, whether
+    /// the engine or the redundancy code is at fault.
+    fn reserved_pool_path_failed_create() {
+        let engine = Rc::new(RefCell::new(SimEngine::default()));
+        let (mut tree, base_path) = get_base_tree(DbusContext::new(engine.clone()));
+        let dbus_context = tree.get_data().clone();
+
+        let name = "name";
+        let reserved = reserve_dbus_pool_path(&dbus_context, name, None);
+        apply_actions(&mut tree, &dbus_context);
+        assert!(create_pool_object(&dbus_context,
+                                   base_path.clone(),
+                                   name,
+                                   &[Path::new("/s/d")],
+                                   Ok(Some(u16::from(Redundancy::RAID1))),
+                                   false,
+                                   false,
+                                   false)
+                        .is_err());
+        apply_actions(&mut tree, &dbus_context);
+        assert!(tree.get(&reserved).is_none());
+        assert!(dbus_context.reserved_pools.borrow().is_empty());
+
+        let reserved = reserve_dbus_pool_path(&dbus_context, name, None);
+        apply_actions(&mut tree, &dbus_context);
+        let redundancy = Err(EngineError::Engine(ErrorEnum::Invalid, "redundancy".into()));
+        assert!(create_pool_object(&dbus_context,
+                                   base_path,
+                                   name,
+                                   &[Path::new("/s/d")],
+                                   redundancy,
+                                   false,
+                                   false,
+                                   false)
+                        .is_err());
+        apply_actions(&mut tree, &dbus_context);
+        assert!(tree.get(&reserved).is_none());
+        assert!(engine.borrow().pools().is_empty());
+    }
+
+    #[test]
+    /// When a client disconnects, the paths it reserved are released and
+    /// those reserved by other clients are kept.
+    fn reserved_pool_path_disconnect() {
+        let engine = Rc::new(RefCell::new(SimEngine::default()));
+        let (mut tree, _) = get_base_tree(DbusContext::new(engine));
+        let dbus_context = tree.get_data().clone();
+
+        let client = ":1.1";
+        let released = reserve_dbus_pool_path(&dbus_context, "a", Some(client));
+        let kept = reserve_dbus_pool_path(&dbus_context, "b", Some(":1.2"));
+        apply_actions(&mut tree, &dbus_context);
+
+        let signal = |new_owner: &str| {
+            Message::signal(&"/org/freedesktop/DBus".into(),
+                            &"org.freedesktop.DBus".into(),
+                            &Member::new("NameOwnerChanged").unwrap())
+                    .append3(client, client, new_owner)
+        };
+        assert_eq!(disconnected_client(&signal(client)), None);
+        let disconnected = disconnected_client(&signal("")).unwrap();
+        assert_eq!(disconnected, client);
+
+        release_dbus_pool_paths(&dbus_context, &disconnected);
+        apply_actions(&mut tree, &dbus_context);
+        assert!(tree.get(&released).is_none());
+        assert!(tree.get(&kept).is_some());
+        assert!(!dbus_context.reserved_pools.borrow().contains_key("a"));
+        assert!(dbus_context.reserved_pools.borrow().contains_key("b"));
+    }
+
+    #[test]
+    /// A pool created without a redundancy has the default redundancy.
+    fn default_redundancy() {
+        let engine = Rc::new(RefCell::new(SimEngine::default()));
+        let dbus_context = DbusContext::new(engine.clone());
+        dbus_context
+            .default_redundancy
+            .set(u16::from(Redundancy::RAID1));
+
+        let redundancy = dbus_context.redundancy_or_default(None);
+        let (uuid, _) = engine
+            .borrow_mut()
+            .create_pool("name",
+                         &[Path::new("/s/a"), Path::new("/s/b")],
+                         Some(redundancy),
+                         false,
+                         false,
+                         false)
+            .unwrap();
+        assert_eq!(*engine.borrow().get_pool(&uuid).unwrap().redundancy(),
+                   Redundancy::RAID1);
+    }
+
+    #[test]
+    /// Adding devices through AddDevs changes the pool's capacity, and
+    /// yields a PropertiesChanged signal, on the pool's path, carrying the
+    /// new values of its capacity properties.
+    fn add_devs_capacity_changed() {
+        let engine = Rc::new(RefCell::new(SimEngine::default()));
+        let (mut tree, base_path) = get_base_tree(DbusContext::new(engine.clone()));
+        let dbus_context = tree.get_data().clone();
+
+        let (uuid, _) = engine
+            .borrow_mut()
+            .create_pool("name", &[Path::new("/s/d")], None, false, false, false)
+            .unwrap();
+        let pool_path = create_dbus_pool(&dbus_context, base_path, uuid, None);
+        apply_actions(&mut tree, &dbus_context);
+
+        let pool_uuid = tree.get(&pool_path)
+            .unwrap()
+            .get_data()
+            .as_ref()
+            .unwrap()
+            .uuid;
+        let mut engine = engine.borrow_mut();
+        let pool = engine.get_mut_pool(&pool_uuid).unwrap();
+        let size = pool.total_physical_size();
+        let capacity = pool.logical_capacity();
+
+        let (_, signal) = add_blockdevs_signalled(pool, &pool_path, &[Path::new("/s/e")], false)
+            .unwrap();
+        assert!(pool.total_physical_size() > size);
+        assert!(pool.logical_capacity() > capacity);
+
+        assert_eq!(&*signal.interface().unwrap(), "org.freedesktop.DBus.Properties");
+        assert_eq!(&*signal.member().unwrap(), "PropertiesChanged");
+        assert_eq!(signal.path(), Some(pool_path));
+
+        let items = signal.get_items();
+        assert_eq!(items[0], MessageItem::Str("org.storage.stratis1.pool".into()));
+        let total_physical_size = match items[1] {
+            MessageItem::Array(ref changed, _) => {
+                changed
+                    .iter()
+                    .filter_map(|entry| match *entry {
+                                    MessageItem::DictEntry(ref name, ref value) => {
+                                        if **name == MessageItem::Str("TotalPhysicalSize".into()) {
+                                            Some((**value).clone())
+                                        } else {
+                                            None
+                                        }
+                                    }
+                                    _ => None,
+                                })
+                    .next()
+            }
+            _ => None,
+        };
+        let expected = format!("{}", *pool.total_physical_size());
+        assert_eq!(total_physical_size,
+                   Some(MessageItem::Variant(Box::new(MessageItem::Str(expected)))));
+    }
 }
@@ -17,7 +17,7 @@ use dbus::tree::PropInfo;
 
 use uuid::Uuid;
 
-use engine::RenameAction;
+use engine::{EngineResult, FilesystemUuid, Pool, RenameAction};
 
 use super::super::engine::Filesystem;
 
@@ -45,6 +45,16 @@ pub fn create_dbus_filesystem<'a>(dbus_context: &DbusContext,
         .out_arg(("return_code", "q"))
         .out_arg(("return_string", "s"));
 
+    let activate_method = f.method("Activate", (), activate_filesystem)
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let deactivate_method = f.method("Deactivate", (), deactivate_filesystem)
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
     let devnode_property = f.property::<&str, _>("Devnode", ())
         .access(Access::Read)
         .emits_changed(EmitsChangedSignal::Const)
@@ -75,6 +85,8 @@ pub fn create_dbus_filesystem<'a>(dbus_context: &DbusContext,
         .introspectable()
         .add(f.interface(interface_name, ())
                  .add_m(rename_method)
+                 .add_m(activate_method)
+                 .add_m(deactivate_method)
                  .add_p(devnode_property)
                  .add_p(name_property)
                  .add_p(pool_property)
@@ -133,6 +145,52 @@ fn rename_filesystem(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
     Ok(vec![msg])
 }
 
+/// Apply action to the filesystem designated by the implicit object path
+/// argument and place its boolean result on the D-Bus.
+fn change_filesystem_activation<F>(m: &MethodInfo<MTFn<TData>, TData>, action: F) -> MethodResult
+    where F: Fn(&mut Pool, &FilesystemUuid) -> EngineResult<bool>
+{
+    let message: &Message = m.msg;
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
+
+    let filesystem_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let filesystem_data = get_data!(filesystem_path; default_return; return_message);
+
+    let pool_path = get_parent!(m; filesystem_data; default_return; return_message);
+    let pool_uuid = &get_data!(pool_path; default_return; return_message).uuid;
+
+    let mut engine = dbus_context.engine.borrow_mut();
+    let pool = get_mut_pool!(engine; pool_uuid; default_return; return_message);
+
+    let msg = match action(pool, &filesystem_data.uuid) {
+        Ok(changed) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(changed), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+
+    Ok(vec![msg])
+}
+
+fn activate_filesystem(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    change_filesystem_activation(m, |pool, uuid| pool.activate_filesystem(uuid))
+}
+
+fn deactivate_filesystem(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    change_filesystem_activation(m, |pool, uuid| pool.deactivate_filesystem(uuid))
+}
+
 /// Get a filesystem property and place it on the D-Bus. The property is
 /// found by means of the getter method which takes a reference to a
 /// Filesystem and obtains the property from the filesystem.
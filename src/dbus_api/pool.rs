@@ -3,7 +3,7 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::vec::Vec;
 
 use dbus;
@@ -24,18 +24,21 @@ use uuid::Uuid;
 
 use devicemapper::Sectors;
 
-use engine::{Pool, RenameAction};
+use engine::{EngineResult, Pool, RenameAction};
 
 use super::filesystem::create_dbus_filesystem;
 use super::types::{DbusContext, DbusErrorEnum, OPContext, TData};
 
 use super::util::STRATIS_BASE_PATH;
 use super::util::STRATIS_BASE_SERVICE;
+use super::util::check_expected_uuid;
 use super::util::code_to_message_items;
 use super::util::engine_to_dbus_err;
 use super::util::get_next_arg;
 use super::util::get_uuid;
 use super::util::ok_message_items;
+use super::util::properties_changed_signal;
+use super::util::tuple_to_option;
 
 
 fn create_filesystems(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
@@ -43,6 +46,29 @@ fn create_filesystems(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
     let mut iter = message.iter_init();
 
     let filesystems: Array<&str, _> = get_next_arg(&mut iter, 0)?;
+
+    create_filesystems_reply(m, &filesystems.collect::<Vec<&str>>(), false)
+}
+
+fn create_filesystems_with_options(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let mut iter = message.iter_init();
+
+    let filesystems: Array<&str, _> = get_next_arg(&mut iter, 0)?;
+    let require_redundancy: bool = get_next_arg(&mut iter, 1)?;
+
+    create_filesystems_reply(m,
+                             &filesystems.collect::<Vec<&str>>(),
+                             require_redundancy)
+}
+
+/// Create filesystems with the given names in the pool, and construct the
+/// reply to a filesystem creation method.
+fn create_filesystems_reply(m: &MethodInfo<MTFn<TData>, TData>,
+                            filesystems: &[&str],
+                            require_redundancy: bool)
+                            -> MethodResult {
+    let message: &Message = m.msg;
     let dbus_context = m.tree.get_data();
 
     let object_path = m.path.get_name();
@@ -60,8 +86,10 @@ fn create_filesystems(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
 
     let result =
         pool.create_filesystems(&filesystems
-                                     .map(|x| (x, None))
-                                     .collect::<Vec<(&str, Option<Sectors>)>>());
+                                     .iter()
+                                     .map(|x| (*x, None))
+                                     .collect::<Vec<(&str, Option<Sectors>)>>(),
+                                require_redundancy);
 
     let msg = match result {
         Ok(ref infos) => {
@@ -148,6 +176,11 @@ fn destroy_filesystems(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
     Ok(vec![msg])
 }
 
+/// The name of the interface implemented by pool objects.
+fn pool_interface_name() -> String {
+    format!("{}.{}", STRATIS_BASE_SERVICE, "pool")
+}
+
 fn add_devs(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
     let message: &Message = m.msg;
     let mut iter = message.iter_init();
@@ -171,8 +204,8 @@ fn add_devs(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
 
     let blockdevs = devs.map(|x| Path::new(x)).collect::<Vec<&Path>>();
 
-    let msg = match pool.add_blockdevs(&blockdevs, force) {
-        Ok(devnodes) => {
+    let msg = match add_blockdevs_signalled(pool, object_path, &blockdevs, force) {
+        Ok((devnodes, signal)) => {
             let paths = devnodes
                 .iter()
                 .map(|d| {
@@ -182,7 +215,10 @@ fn add_devs(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
                      });
             let paths = paths.map(MessageItem::Str).collect();
             let (rc, rs) = ok_message_items();
-            return_message.append3(MessageItem::Array(paths, return_sig.into()), rc, rs)
+            return Ok(vec![return_message.append3(MessageItem::Array(paths, return_sig.into()),
+                                                  rc,
+                                                  rs),
+                           signal]);
         }
         Err(x) => {
             let (rc, rs) = engine_to_dbus_err(&x);
@@ -193,12 +229,50 @@ fn add_devs(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
     Ok(vec![msg])
 }
 
+/// Add blockdevs to the pool at object_path, as AddDevs does. On success,
+/// return the devnodes added, with a PropertiesChanged signal for the
+/// pool's capacity properties. AddDevs is the only method which changes
+/// them, so these are the only properties of the pool which emit it.
+pub fn add_blockdevs_signalled(pool: &mut Pool,
+                               object_path: &dbus::Path,
+                               blockdevs: &[&Path],
+                               force: bool)
+                               -> EngineResult<(Vec<PathBuf>, Message)> {
+    let devnodes = pool.add_blockdevs(blockdevs, force)?;
+    let changed = vec![("TotalPhysicalSize".into(),
+                        MessageItem::Str(format!("{}", *pool.total_physical_size()))),
+                       ("LogicalCapacity".into(),
+                        MessageItem::Str(format!("{}", *pool.logical_capacity())))];
+    let signal = properties_changed_signal(object_path, &pool_interface_name(), changed);
+    Ok((devnodes, signal))
+}
+
 fn rename_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
     let message: &Message = m.msg;
     let mut iter = message.iter_init();
 
     let new_name: &str = get_next_arg(&mut iter, 0)?;
 
+    rename_pool_reply(m, new_name, None)
+}
+
+fn rename_pool_with_expected_uuid(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let mut iter = message.iter_init();
+
+    let new_name: &str = get_next_arg(&mut iter, 0)?;
+    let expected_uuid: (bool, &str) = get_next_arg(&mut iter, 1)?;
+
+    rename_pool_reply(m, new_name, tuple_to_option(expected_uuid))
+}
+
+/// Rename the pool, if it has the expected UUID, and construct the reply
+/// to a pool renaming method.
+fn rename_pool_reply(m: &MethodInfo<MTFn<TData>, TData>,
+                     new_name: &str,
+                     expected_uuid: Option<&str>)
+                     -> MethodResult {
+    let message: &Message = m.msg;
     let dbus_context = m.tree.get_data();
     let object_path = m.path.get_name();
     let return_message = message.method_return();
@@ -209,6 +283,11 @@ fn rename_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
         .expect("implicit argument must be in tree");
     let pool_uuid = get_data!(pool_path; default_return; return_message).uuid;
 
+    if let Err((rc, rs)) = check_expected_uuid(expected_uuid, &pool_uuid) {
+        let (rc, rs) = code_to_message_items(rc, rs);
+        return Ok(vec![return_message.append3(default_return, rc, rs)]);
+    }
+
     let msg = match dbus_context
               .engine
               .borrow_mut()
@@ -235,6 +314,270 @@ fn rename_pool(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
     Ok(vec![msg])
 }
 
+fn set_hidden(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let mut iter = message.iter_init();
+
+    let hidden: bool = get_next_arg(&mut iter, 0)?;
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; default_return; return_message).uuid;
+
+    let mut engine = dbus_context.engine.borrow_mut();
+    let pool = get_mut_pool!(engine; pool_uuid; default_return; return_message);
+
+    let action = pool.hidden() != hidden;
+    pool.set_hidden(hidden);
+
+    let (rc, rs) = ok_message_items();
+    Ok(vec![return_message.append3(MessageItem::Bool(action), rc, rs)])
+}
+
+fn set_watermarks(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let mut iter = message.iter_init();
+
+    let data: u8 = get_next_arg(&mut iter, 0)?;
+    let metadata: u8 = get_next_arg(&mut iter, 1)?;
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; default_return; return_message).uuid;
+
+    let mut engine = dbus_context.engine.borrow_mut();
+    let pool = get_mut_pool!(engine; pool_uuid; default_return; return_message);
+
+    let old_data = pool.data_low_watermark();
+    let action = old_data != data || pool.metadata_low_watermark() != metadata;
+
+    // Restore the data watermark if the metadata watermark is rejected, so
+    // that either both are set or neither is.
+    let result = pool.set_data_low_watermark(data)
+        .and_then(|_| {
+                      pool.set_metadata_low_watermark(metadata)
+                          .map_err(|err| {
+                                       let _ = pool.set_data_low_watermark(old_data);
+                                       err
+                                   })
+                  });
+
+    let msg = match result {
+        Ok(_) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(action), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+fn swap_filesystem_names(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let mut iter = message.iter_init();
+
+    let filesystem_a: dbus::Path<'static> = get_next_arg(&mut iter, 0)?;
+    let filesystem_b: dbus::Path<'static> = get_next_arg(&mut iter, 1)?;
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Bool(false);
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; default_return; return_message).uuid;
+
+    let mut filesystem_uuids = Vec::new();
+    for op in &[filesystem_a, filesystem_b] {
+        match m.tree.get(op) {
+            Some(filesystem_path) => {
+                filesystem_uuids
+                    .push(get_data!(filesystem_path; default_return; return_message).uuid)
+            }
+            None => {
+                let message = format!("no filesystem for object path {}", op);
+                let (rc, rs) = code_to_message_items(DbusErrorEnum::NOTFOUND, message);
+                return Ok(vec![return_message.append3(default_return, rc, rs)]);
+            }
+        }
+    }
+
+    let mut engine = dbus_context.engine.borrow_mut();
+    let pool = get_mut_pool!(engine; pool_uuid; default_return; return_message);
+
+    let msg = match pool.swap_filesystem_names(&filesystem_uuids[0], &filesystem_uuids[1]) {
+        Ok(_) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(MessageItem::Bool(true), rc, rs)
+        }
+        Err(err) => {
+            let (rc, rs) = engine_to_dbus_err(&err);
+            let (rc, rs) = code_to_message_items(rc, rs);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+/// Place a value describing the pool designated by the implicit object
+/// path argument on the D-Bus. The value is obtained from the pool by
+/// means of the getter method; default_return is used if there is no pool.
+fn get_pool_item<F>(m: &MethodInfo<MTFn<TData>, TData>,
+                    default_return: MessageItem,
+                    getter: F)
+                    -> MethodResult
+    where F: Fn(&Pool) -> MessageItem
+{
+    let message: &Message = m.msg;
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; default_return; return_message).uuid;
+
+    let msg = match dbus_context.engine.borrow().get_pool(pool_uuid) {
+        Some(pool) => {
+            let (rc, rs) = ok_message_items();
+            return_message.append3(getter(pool), rc, rs)
+        }
+        None => {
+            let message = format!("engine does not know about pool with uuid {}", pool_uuid);
+            let (rc, rs) = code_to_message_items(DbusErrorEnum::INTERNAL_ERROR, message);
+            return_message.append3(default_return, rc, rs)
+        }
+    };
+    Ok(vec![msg])
+}
+
+/// Place a string describing the pool designated by the implicit object
+/// path argument on the D-Bus.
+fn get_pool_string<F>(m: &MethodInfo<MTFn<TData>, TData>, getter: F) -> MethodResult
+    where F: Fn(&Pool) -> String
+{
+    get_pool_item(m, MessageItem::Str("".into()), |p| MessageItem::Str(getter(p)))
+}
+
+fn get_config_hash(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    get_pool_string(m, |p| p.config_hash())
+}
+
+fn get_status_detail(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    get_pool_string(m, |p| p.status_detail())
+}
+
+fn get_device_status_counts(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let return_sig = "{sq}";
+    get_pool_item(m, MessageItem::Array(vec![], return_sig.into()), |p| {
+        let counts = p.device_status_counts()
+            .iter()
+            .map(|(status, count)| {
+                     MessageItem::DictEntry(Box::new(MessageItem::Str(format!("{}", status))),
+                                            Box::new(MessageItem::UInt16(*count as u16)))
+                 })
+            .collect();
+        MessageItem::Array(counts, return_sig.into())
+    })
+}
+
+/// The D-Bus signature of the IO stats which GetFilesystemIoStats returns.
+pub const IO_STATS_SIG: &'static str = "(tttt)";
+
+/// The IO stats of the pool's filesystem name, as GetFilesystemIoStats
+/// returns them, with the return code and string.
+pub fn filesystem_io_stats_items(pool: &Pool,
+                                 name: &str)
+                                 -> (MessageItem, MessageItem, MessageItem) {
+    let io_stats = pool.filesystem_io_stats(name);
+    let (rc, rs) = match io_stats {
+        Ok(_) => ok_message_items(),
+        Err(ref err) => {
+            let (rc, rs) = engine_to_dbus_err(err);
+            code_to_message_items(rc, rs)
+        }
+    };
+    let io_stats = io_stats.unwrap_or_default();
+    let io_stats = MessageItem::Struct(vec![MessageItem::UInt64(*io_stats.read_bytes),
+                                            MessageItem::UInt64(*io_stats.write_bytes),
+                                            MessageItem::UInt64(io_stats.read_ops),
+                                            MessageItem::UInt64(io_stats.write_ops)]);
+    (io_stats, rc, rs)
+}
+
+fn get_filesystem_io_stats(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let mut iter = message.iter_init();
+
+    let name: &str = get_next_arg(&mut iter, 0)?;
+
+    let dbus_context = m.tree.get_data();
+    let object_path = m.path.get_name();
+    let return_message = message.method_return();
+    let default_return = MessageItem::Struct(vec![MessageItem::UInt64(0),
+                                                  MessageItem::UInt64(0),
+                                                  MessageItem::UInt64(0),
+                                                  MessageItem::UInt64(0)]);
+
+    let pool_path = m.tree
+        .get(object_path)
+        .expect("implicit argument must be in tree");
+    let pool_uuid = &get_data!(pool_path; default_return; return_message).uuid;
+
+    let engine = dbus_context.engine.borrow();
+    let pool = get_pool!(engine; pool_uuid; default_return; return_message);
+
+    let (io_stats, rc, rs) = filesystem_io_stats_items(pool, name);
+    Ok(vec![return_message.append3(io_stats, rc, rs)])
+}
+
+fn list_filesystems_by_size(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let message: &Message = m.msg;
+    let mut iter = message.iter_init();
+
+    let min_size: u64 = get_next_arg(&mut iter, 0)?;
+    let descending: bool = get_next_arg(&mut iter, 1)?;
+
+    let return_sig = "(st)";
+    get_pool_item(m, MessageItem::Array(vec![], return_sig.into()), |p| {
+        let sizes = p.filesystems_by_size(Sectors(min_size), descending)
+            .into_iter()
+            .map(|(name, size)| {
+                     MessageItem::Struct(vec![MessageItem::Str(name), MessageItem::UInt64(*size)])
+                 })
+            .collect();
+        MessageItem::Array(sizes, return_sig.into())
+    })
+}
+
+fn get_watermarks(m: &MethodInfo<MTFn<TData>, TData>) -> MethodResult {
+    let default_return = MessageItem::Struct(vec![MessageItem::Byte(0), MessageItem::Byte(0)]);
+    get_pool_item(m, default_return, |p| {
+        MessageItem::Struct(vec![MessageItem::Byte(p.data_low_watermark()),
+                                 MessageItem::Byte(p.metadata_low_watermark())])
+    })
+}
+
 /// Get a pool property and place it on the D-Bus. The property is
 /// found by means of the getter method which takes a reference to a
 /// Pool and obtains the property from the pool.
@@ -298,9 +641,93 @@ fn get_pool_total_physical_size(i: &mut IterAppend,
                       |p| Ok(MessageItem::Str(format!("{}", *p.total_physical_size()))))
 }
 
+fn get_pool_dedup_ratio(i: &mut IterAppend,
+                        p: &PropInfo<MTFn<TData>, TData>)
+                        -> Result<(), MethodErr> {
+    get_pool_property(i, p, |p| {
+        let ratio = p.dedup_ratio();
+        Ok(MessageItem::Struct(vec![MessageItem::Bool(ratio.is_some()),
+                                    MessageItem::Double(ratio.unwrap_or(0.0))]))
+    })
+}
+
+fn get_pool_logical_capacity(i: &mut IterAppend,
+                             p: &PropInfo<MTFn<TData>, TData>)
+                             -> Result<(), MethodErr> {
+    get_pool_property(i,
+                      p,
+                      |p| Ok(MessageItem::Str(format!("{}", *p.logical_capacity()))))
+}
+
+fn get_pool_largest_free_extent(i: &mut IterAppend,
+                                p: &PropInfo<MTFn<TData>, TData>)
+                                -> Result<(), MethodErr> {
+    get_pool_property(i,
+                      p,
+                      |p| Ok(MessageItem::Str(format!("{}", *p.largest_free_extent()))))
+}
+
+fn get_pool_redundancy_overhead(i: &mut IterAppend,
+                                p: &PropInfo<MTFn<TData>, TData>)
+                                -> Result<(), MethodErr> {
+    get_pool_property(i,
+                      p,
+                      |p| Ok(MessageItem::Str(format!("{}", *p.redundancy_overhead()))))
+}
+
+/// Register a placeholder object for a pool that is about to be created,
+/// so that its object path is known to clients before the pool exists.
+/// If a path has already been reserved for name, return that path.
+/// The placeholder is released by release_dbus_pool_paths() if owner, the
+/// client that reserved it, disconnects before creating the pool.
+pub fn reserve_dbus_pool_path(dbus_context: &DbusContext,
+                              name: &str,
+                              owner: Option<&str>)
+                              -> dbus::Path<'static> {
+    if let Some(&(ref path, _)) = dbus_context.reserved_pools.borrow().get(name) {
+        return path.clone();
+    }
+
+    let f = Factory::new_fn();
+
+    let object_name = format!("{}/{}",
+                              STRATIS_BASE_PATH,
+                              dbus_context.get_next_id().to_string());
+
+    let object_path = f.object_path(object_name, None).introspectable();
+
+    let path = object_path.get_name().to_owned();
+    dbus_context.actions.borrow_mut().push_add(object_path);
+    dbus_context
+        .reserved_pools
+        .borrow_mut()
+        .insert(name.to_owned(), (path.clone(), owner.map(|o| o.to_owned())));
+    path
+}
+
+/// Remove the placeholders for all paths reserved by owner.
+pub fn release_dbus_pool_paths(dbus_context: &DbusContext, owner: &str) {
+    let mut reserved_pools = dbus_context.reserved_pools.borrow_mut();
+    let names: Vec<String> = reserved_pools
+        .iter()
+        .filter(|&(_, &(_, ref o))| o.as_ref().map(|o| o.as_str()) == Some(owner))
+        .map(|(name, _)| name.clone())
+        .collect();
+    for name in names {
+        let (path, _) = reserved_pools
+            .remove(&name)
+            .expect("name was found in reserved_pools above");
+        dbus_context.actions.borrow_mut().push_remove(path);
+    }
+}
+
+/// Create the D-Bus object for the pool with the given uuid. If reserved
+/// is a path obtained from reserve_dbus_pool_path(), the placeholder at
+/// that path is replaced by the pool object.
 pub fn create_dbus_pool<'a>(dbus_context: &DbusContext,
                             parent: dbus::Path<'static>,
-                            uuid: Uuid)
+                            uuid: Uuid,
+                            reserved: Option<dbus::Path<'static>>)
                             -> dbus::Path<'a> {
 
     let f = Factory::new_fn();
@@ -311,6 +738,16 @@ pub fn create_dbus_pool<'a>(dbus_context: &DbusContext,
         .out_arg(("return_code", "q"))
         .out_arg(("return_string", "s"));
 
+    let create_filesystems_with_options_method =
+        f.method("CreateFilesystemsWithOptions",
+                 (),
+                 create_filesystems_with_options)
+            .in_arg(("specs", "as"))
+            .in_arg(("require_redundancy", "b"))
+            .out_arg(("filesystems", "a(os)"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
     let destroy_filesystems_method = f.method("DestroyFilesystems", (), destroy_filesystems)
         .in_arg(("filesystems", "ao"))
         .out_arg(("results", "as"))
@@ -324,12 +761,97 @@ pub fn create_dbus_pool<'a>(dbus_context: &DbusContext,
         .out_arg(("return_code", "q"))
         .out_arg(("return_string", "s"));
 
+    let get_config_hash_method = f.method("GetConfigHash", (), get_config_hash)
+        .out_arg(("config_hash", "s"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_status_detail_method = f.method("GetStatusDetail", (), get_status_detail)
+        .out_arg(("status_detail", "s"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let get_device_status_counts_method =
+        f.method("GetDeviceStatusCounts", (), get_device_status_counts)
+            .out_arg(("counts", "a{sq}"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let get_filesystem_io_stats_method =
+        f.method("GetFilesystemIoStats", (), get_filesystem_io_stats)
+            .in_arg(("name", "s"))
+            .out_arg(("io_stats", IO_STATS_SIG))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let list_filesystems_by_size_method =
+        f.method("ListFilesystemsBySize", (), list_filesystems_by_size)
+            .in_arg(("min_size", "t"))
+            .in_arg(("descending", "b"))
+            .out_arg(("filesystems", "a(st)"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let get_watermarks_method = f.method("GetWatermarks", (), get_watermarks)
+        .out_arg(("watermarks", "(yy)"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let set_watermarks_method = f.method("SetWatermarks", (), set_watermarks)
+        .in_arg(("data", "y"))
+        .in_arg(("metadata", "y"))
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
+    let swap_filesystem_names_method =
+        f.method("SwapFilesystemNames", (), swap_filesystem_names)
+            .in_arg(("filesystem_a", "o"))
+            .in_arg(("filesystem_b", "o"))
+            .out_arg(("action", "b"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let set_hidden_method = f.method("SetHidden", (), set_hidden)
+        .in_arg(("hidden", "b"))
+        .out_arg(("action", "b"))
+        .out_arg(("return_code", "q"))
+        .out_arg(("return_string", "s"));
+
     let rename_method = f.method("SetName", (), rename_pool)
         .in_arg(("name", "s"))
         .out_arg(("action", "b"))
         .out_arg(("return_code", "q"))
         .out_arg(("return_string", "s"));
 
+    let rename_with_expected_uuid_method =
+        f.method("SetNameWithExpectedUuid", (), rename_pool_with_expected_uuid)
+            .in_arg(("name", "s"))
+            .in_arg(("expected_uuid", "(bs)"))
+            .out_arg(("action", "b"))
+            .out_arg(("return_code", "q"))
+            .out_arg(("return_string", "s"));
+
+    let dedup_ratio_property = f.property::<(bool, f64), _>("DedupRatio", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_pool_dedup_ratio);
+
+    let largest_free_extent_property = f.property::<&str, _>("LargestFreeExtent", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_pool_largest_free_extent);
+
+    let logical_capacity_property = f.property::<&str, _>("LogicalCapacity", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::True)
+        .on_get(get_pool_logical_capacity);
+
+    let redundancy_overhead_property = f.property::<&str, _>("RedundancyOverhead", ())
+        .access(Access::Read)
+        .emits_changed(EmitsChangedSignal::False)
+        .on_get(get_pool_redundancy_overhead);
+
     let name_property = f.property::<&str, _>("Name", ())
         .access(Access::Read)
         .emits_changed(EmitsChangedSignal::False)
@@ -337,7 +859,7 @@ pub fn create_dbus_pool<'a>(dbus_context: &DbusContext,
 
     let total_physical_size_property = f.property::<&str, _>("TotalPhysicalSize", ())
         .access(Access::Read)
-        .emits_changed(EmitsChangedSignal::False)
+        .emits_changed(EmitsChangedSignal::True)
         .on_get(get_pool_total_physical_size);
 
     let total_physical_used_property = f.property::<&str, _>("TotalPhysicalUsed", ())
@@ -350,20 +872,44 @@ pub fn create_dbus_pool<'a>(dbus_context: &DbusContext,
         .emits_changed(EmitsChangedSignal::Const)
         .on_get(get_uuid);
 
-    let object_name = format!("{}/{}",
-                              STRATIS_BASE_PATH,
-                              dbus_context.get_next_id().to_string());
+    let object_name: dbus::Path<'static> = match reserved {
+        Some(path) => {
+            dbus_context.actions.borrow_mut().push_remove(path.clone());
+            path
+        }
+        None => {
+            format!("{}/{}",
+                    STRATIS_BASE_PATH,
+                    dbus_context.get_next_id().to_string())
+                    .into()
+        }
+    };
 
-    let interface_name = format!("{}.{}", STRATIS_BASE_SERVICE, "pool");
+    let interface_name = pool_interface_name();
 
     let object_path = f.object_path(object_name, Some(OPContext::new(parent, uuid)))
         .introspectable()
         .add(f.interface(interface_name, ())
                  .add_m(create_filesystems_method)
+                 .add_m(create_filesystems_with_options_method)
                  .add_m(destroy_filesystems_method)
                  .add_m(add_devs_method)
+                 .add_m(get_config_hash_method)
+                 .add_m(get_status_detail_method)
+                 .add_m(get_device_status_counts_method)
+                 .add_m(get_filesystem_io_stats_method)
+                 .add_m(get_watermarks_method)
+                 .add_m(list_filesystems_by_size_method)
                  .add_m(rename_method)
+                 .add_m(rename_with_expected_uuid_method)
+                 .add_m(set_hidden_method)
+                 .add_m(set_watermarks_method)
+                 .add_m(swap_filesystem_names_method)
+                 .add_p(dedup_ratio_property)
+                 .add_p(largest_free_extent_property)
+                 .add_p(logical_capacity_property)
                  .add_p(name_property)
+                 .add_p(redundancy_overhead_property)
                  .add_p(total_physical_size_property)
                  .add_p(total_physical_used_property)
                  .add_p(uuid_property));
@@ -372,3 +918,59 @@ pub fn create_dbus_pool<'a>(dbus_context: &DbusContext,
     dbus_context.actions.borrow_mut().push_add(object_path);
     path
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use dbus::MessageItem;
+
+    use devicemapper::Bytes;
+
+    use engine::{Engine, IoStats, SimEngine};
+
+    use super::super::types::DbusErrorEnum;
+
+    use super::{IO_STATS_SIG, filesystem_io_stats_items};
+
+    #[test]
+    /// GetFilesystemIoStats returns the IO stats of the named filesystem of
+    /// the pool, in the signature the method declares; for a filesystem the
+    /// pool does not have, it returns zeroes and NOTFOUND.
+    fn get_filesystem_io_stats() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("name", &[Path::new("/s/d")], None, false, false, false)
+            .unwrap();
+        engine
+            .get_mut_pool(&uuid)
+            .unwrap()
+            .create_filesystems(&[("fs", None)], false)
+            .unwrap();
+        let io_stats = IoStats {
+            read_bytes: Bytes(4096),
+            write_bytes: Bytes(512),
+            read_ops: 2,
+            write_ops: 1,
+        };
+        engine.set_filesystem_io_stats(&uuid, "fs", io_stats);
+        let pool = engine.get_pool(&uuid).unwrap();
+
+        let (stats, rc, _) = filesystem_io_stats_items(pool, "fs");
+        assert_eq!(stats.type_sig(), IO_STATS_SIG);
+        assert_eq!(stats,
+                   MessageItem::Struct(vec![MessageItem::UInt64(4096),
+                                            MessageItem::UInt64(512),
+                                            MessageItem::UInt64(2),
+                                            MessageItem::UInt64(1)]));
+        assert_eq!(rc, MessageItem::UInt16(DbusErrorEnum::OK as u16));
+
+        let (stats, rc, _) = filesystem_io_stats_items(pool, "other");
+        assert_eq!(stats,
+                   MessageItem::Struct(vec![MessageItem::UInt64(0),
+                                            MessageItem::UInt64(0),
+                                            MessageItem::UInt64(0),
+                                            MessageItem::UInt64(0)]));
+        assert_eq!(rc, MessageItem::UInt16(DbusErrorEnum::NOTFOUND as u16));
+    }
+}
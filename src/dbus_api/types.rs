@@ -3,6 +3,7 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::collections::vec_deque::{Drain, VecDeque};
 use std::convert::From;
 use std::rc::Rc;
@@ -12,7 +13,7 @@ use dbus::tree::{DataType, MTFn, ObjectPath};
 
 use uuid::Uuid;
 
-use engine::Engine;
+use engine::{Engine, Redundancy};
 
 custom_derive! {
     #[derive(Copy, Clone, EnumDisplay,
@@ -29,6 +30,10 @@ custom_derive! {
         INTERNAL_ERROR,
         NIX_ERROR,
         NOTFOUND,
+        PRECONDITION_FAILED,
+        DEVICE_SIZE_MISMATCH,
+        INSUFFICIENT_SPACE,
+        NOT_REDUNDANT,
     }
 }
 
@@ -50,6 +55,10 @@ impl DbusErrorEnum {
             DbusErrorEnum::IO_ERROR => "IO error during operation",
             DbusErrorEnum::NIX_ERROR => "System error during operation",
             DbusErrorEnum::NOTFOUND => "Not found",
+            DbusErrorEnum::PRECONDITION_FAILED => "Object is not the one expected",
+            DbusErrorEnum::DEVICE_SIZE_MISMATCH => "Device sizes differ too much",
+            DbusErrorEnum::INSUFFICIENT_SPACE => "Not enough space for operation",
+            DbusErrorEnum::NOT_REDUNDANT => "Pool is not redundant",
         }
     }
 }
@@ -83,6 +92,11 @@ pub struct DbusContext {
     pub next_index: Rc<Cell<u64>>,
     pub engine: Rc<RefCell<Engine>>,
     pub actions: Rc<RefCell<ActionQueue>>,
+    /// Object paths reserved for pools not yet created, keyed by pool name,
+    /// each with the unique bus name of the client that reserved it.
+    pub reserved_pools: Rc<RefCell<HashMap<String, (Path<'static>, Option<String>)>>>,
+    /// The redundancy code used for pools created without one.
+    pub default_redundancy: Rc<Cell<u16>>,
 }
 
 impl DbusContext {
@@ -91,9 +105,16 @@ impl DbusContext {
             actions: Rc::new(RefCell::new(ActionQueue::default())),
             engine: engine,
             next_index: Rc::new(Cell::new(0)),
+            reserved_pools: Rc::new(RefCell::new(HashMap::new())),
+            default_redundancy: Rc::new(Cell::new(u16::from(Redundancy::NONE))),
         }
     }
 
+    /// The redundancy code, if specified, otherwise the default.
+    pub fn redundancy_or_default(&self, redundancy: Option<u16>) -> u16 {
+        redundancy.unwrap_or_else(|| self.default_redundancy.get())
+    }
+
     /// Generates a new id for object paths.
     /// It is assumed that, while Stratisd is running, it will never generate
     /// more than 2^64 object paths. If it turns out that this is a bad
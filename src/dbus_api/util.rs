@@ -5,10 +5,14 @@
 use std::error::Error;
 
 use dbus;
+use dbus::Message;
 use dbus::MessageItem;
+use dbus::RequestNameReply;
 use dbus::arg::{ArgType, Iter, IterAppend};
 use dbus::tree::{MethodErr, MTFn, PropInfo};
 
+use uuid::Uuid;
+
 use engine::{EngineError, ErrorEnum};
 
 use super::types::{DbusErrorEnum, TData};
@@ -33,6 +37,25 @@ pub fn get_next_arg<'a, T>(iter: &mut Iter<'a>, loc: u16) -> Result<T, MethodErr
     Ok(value)
 }
 
+/// Check that uuid is the expected UUID, if one was specified.
+/// Returns PRECONDITION_FAILED if it was specified and is not uuid.
+pub fn check_expected_uuid(expected: Option<&str>,
+                           uuid: &Uuid)
+                           -> Result<(), (DbusErrorEnum, String)> {
+    match expected {
+        None => Ok(()),
+        Some(expected) => {
+            if Uuid::parse_str(expected).ok().as_ref() == Some(uuid) {
+                Ok(())
+            } else {
+                Err((DbusErrorEnum::PRECONDITION_FAILED,
+                     format!("object has uuid {}, not expected uuid {}",
+                             uuid.simple(),
+                             expected)))
+            }
+        }
+    }
+}
 
 /// Translates an engine error to a dbus error.
 pub fn engine_to_dbus_err(err: &EngineError) -> (DbusErrorEnum, String) {
@@ -43,8 +66,11 @@ pub fn engine_to_dbus_err(err: &EngineError) -> (DbusErrorEnum, String) {
                 ErrorEnum::Error => DbusErrorEnum::ERROR,
                 ErrorEnum::AlreadyExists => DbusErrorEnum::ALREADY_EXISTS,
                 ErrorEnum::Busy => DbusErrorEnum::BUSY,
+                ErrorEnum::DeviceSizeMismatch => DbusErrorEnum::DEVICE_SIZE_MISMATCH,
+                ErrorEnum::InsufficientSpace => DbusErrorEnum::INSUFFICIENT_SPACE,
                 ErrorEnum::Invalid => DbusErrorEnum::ERROR,
                 ErrorEnum::NotFound => DbusErrorEnum::NOTFOUND,
+                ErrorEnum::NotRedundant => DbusErrorEnum::NOT_REDUNDANT,
             }
         }
         EngineError::Io(_) => DbusErrorEnum::IO_ERROR,
@@ -69,6 +95,32 @@ pub fn ok_message_items() -> (MessageItem, MessageItem) {
     code_to_message_items(code, code.get_error_string().into())
 }
 
+/// Interpret the reply to a request for the Stratis bus name.
+/// Return an error if the name could not be acquired.
+pub fn name_reply_to_result(reply: RequestNameReply, replace: bool) -> Result<(), dbus::Error> {
+    match reply {
+        RequestNameReply::PrimaryOwner => {
+            if replace {
+                info!("Acquired bus name {}, replacing any previous owner",
+                      STRATIS_BASE_SERVICE);
+            }
+            Ok(())
+        }
+        RequestNameReply::AlreadyOwner => Ok(()),
+        RequestNameReply::InQueue |
+        RequestNameReply::Exists => {
+            let msg = if replace {
+                format!("the current owner of bus name {} refused to release it",
+                        STRATIS_BASE_SERVICE)
+            } else {
+                format!("bus name {} is already owned; use --replace to take it over",
+                        STRATIS_BASE_SERVICE)
+            };
+            Err(dbus::Error::new_custom("org.freedesktop.DBus.Error.Failed", &msg))
+        }
+    }
+}
+
 pub fn default_object_path<'a>() -> dbus::Path<'a> {
     dbus::Path::new("/").expect("'/' is guaranteed to be a valid Path")
 }
@@ -105,3 +157,86 @@ pub fn get_parent(i: &mut IterAppend, p: &PropInfo<MTFn<TData>, TData>) -> Resul
     i.append(MessageItem::ObjectPath(data.parent.clone()));
     Ok(())
 }
+
+/// A PropertiesChanged signal for the properties of interface_name that
+/// are listed in changed with their new values. The signal is emitted on
+/// object_path, so that a client may watch a single object by a match rule
+/// on its path.
+pub fn properties_changed_signal(object_path: &dbus::Path,
+                                 interface_name: &str,
+                                 changed: Vec<(String, MessageItem)>)
+                                 -> Message {
+    let changed = MessageItem::from_dict::<(), _>(changed.into_iter().map(Ok))
+        .expect("iterator yields no errors");
+    Message::signal(object_path,
+                    &"org.freedesktop.DBus.Properties".into(),
+                    &"PropertiesChanged".into())
+            .append3(MessageItem::Str(interface_name.into()),
+                     changed,
+                     MessageItem::Array(vec![], "s".into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use dbus;
+    use dbus::MessageItem;
+    use dbus::RequestNameReply;
+
+    use uuid::Uuid;
+
+    use engine::{EngineError, ErrorEnum};
+
+    use super::super::types::DbusErrorEnum;
+
+    use super::{check_expected_uuid, engine_to_dbus_err, name_reply_to_result,
+                properties_changed_signal};
+
+    #[test]
+    /// Failing to acquire the bus name yields an error, whether or not
+    /// replacement of the current owner was requested.
+    fn name_not_acquired() {
+        assert!(name_reply_to_result(RequestNameReply::Exists, false).is_err());
+        assert!(name_reply_to_result(RequestNameReply::Exists, true).is_err());
+        assert!(name_reply_to_result(RequestNameReply::PrimaryOwner, true).is_ok());
+    }
+
+    #[test]
+    /// A pool that is not redundant has its own error code.
+    fn not_redundant_code() {
+        let err = EngineError::Engine(ErrorEnum::NotRedundant, "not redundant".into());
+        assert!(match engine_to_dbus_err(&err) {
+                    (DbusErrorEnum::NOT_REDUNDANT, _) => true,
+                    _ => false,
+                });
+    }
+
+    #[test]
+    /// An operation whose expected UUID is stale, or unparseable, fails its
+    /// precondition; one with the object's UUID, or with none, passes.
+    fn expected_uuid() {
+        let uuid = Uuid::new_v4();
+        let stale = format!("{}", Uuid::new_v4().simple());
+        assert!(match check_expected_uuid(Some(&stale), &uuid) {
+                    Err((DbusErrorEnum::PRECONDITION_FAILED, _)) => true,
+                    _ => false,
+                });
+        assert!(check_expected_uuid(Some("bogus"), &uuid).is_err());
+        assert!(check_expected_uuid(Some(&format!("{}", uuid.simple())), &uuid).is_ok());
+        assert!(check_expected_uuid(Some(&format!("{}", uuid.hyphenated())), &uuid).is_ok());
+        assert!(check_expected_uuid(None, &uuid).is_ok());
+    }
+
+    #[test]
+    /// A PropertiesChanged signal is emitted on the path of the object
+    /// whose properties changed, not on the manager's path.
+    fn properties_changed_on_object_path() {
+        let pool_path = dbus::Path::new("/org/storage/stratis1/3").unwrap();
+        let signal = properties_changed_signal(&pool_path,
+                                               "org.storage.stratis1.pool",
+                                               vec![("TotalPhysicalSize".into(),
+                                                     MessageItem::Str("2048".into()))]);
+        assert_eq!(signal.path(), Some(pool_path));
+        assert_eq!(&*signal.member().unwrap(), "PropertiesChanged");
+        assert_eq!(signal.get1::<&str>(), Some("org.storage.stratis1.pool"));
+    }
+}
@@ -2,6 +2,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::path::{Path, PathBuf};
 
@@ -10,7 +11,8 @@ use uuid::Uuid;
 use devicemapper::Sectors;
 
 use super::errors::EngineResult;
-use super::types::{FilesystemUuid, PoolUuid, RenameAction};
+use super::types::{DestroyedPoolInfo, DevStatus, EngineKind, FilesystemUuid, IoStats, PoolSpec,
+                   PoolUuid, Redundancy, RenameAction};
 
 pub trait HasUuid: Debug {
     fn uuid(&self) -> &Uuid;
@@ -23,6 +25,12 @@ pub trait HasName: Debug {
 pub trait Filesystem: HasName + HasUuid {
     /// path of the device node
     fn devnode(&self) -> PathBuf;
+
+    /// Whether the device node of this filesystem exists.
+    fn is_active(&self) -> bool;
+
+    /// The logical size of this filesystem's device.
+    fn size(&self) -> Sectors;
 }
 
 pub trait BlockDev: HasUuid {
@@ -36,16 +44,30 @@ pub trait Pool: HasName + HasUuid {
     /// Returns an error if any of the specified names are already in use
     /// for filesystems in this pool. If the same name is passed multiple
     /// times, the size associated with the last item is used.
+    /// If require_redundancy is true, returns an error if the pool's
+    /// redundancy does not protect against the loss of a blockdev.
     fn create_filesystems<'a, 'b>(&'a mut self,
-                                  specs: &[(&'b str, Option<Sectors>)])
+                                  specs: &[(&'b str, Option<Sectors>)],
+                                  require_redundancy: bool)
                                   -> EngineResult<Vec<(&'b str, FilesystemUuid)>>;
 
     /// Adds blockdevs specified by paths to pool.
     /// Returns a list of device nodes corresponding to devices actually added.
     /// Returns an error if a blockdev can not be added because it is owned
     /// or there was an error while reading or writing a blockdev.
+    /// Returns a Busy error if a blockdev is held open by some other process,
+    /// as it is if it already belongs to a pool.
     fn add_blockdevs(&mut self, paths: &[&Path], force: bool) -> EngineResult<Vec<PathBuf>>;
 
+    /// Move all data allocated on the blockdev with devnode path to the
+    /// pool's other blockdevs, and allow no further allocation on it, so
+    /// that it is ready to be removed from the pool.
+    /// Returns an error if the blockdev does not belong to this pool, and an
+    /// InsufficientSpace error if the other blockdevs do not have room for
+    /// its data.
+    /// An evacuated blockdev no longer counts towards the pool's size.
+    fn evacuate_blockdev(&mut self, path: &Path) -> EngineResult<()>;
+
     /// Destroy the pool.
     /// Precondition: All filesystems belonging to this pool must be
     /// unmounted.
@@ -69,6 +91,30 @@ pub trait Pool: HasName + HasUuid {
                          new_name: &str)
                          -> EngineResult<RenameAction>;
 
+    /// Exchange the names of the two filesystems with the given UUIDs.
+    /// All other properties stay with their respective filesystems.
+    /// Returns an error if either filesystem does not belong to this pool.
+    fn swap_filesystem_names(&mut self,
+                             uuid_a: &FilesystemUuid,
+                             uuid_b: &FilesystemUuid)
+                             -> EngineResult<()>;
+
+    /// Activate the filesystem, so that its device node exists.
+    /// Returns true if some action was necessary, otherwise false.
+    /// Returns an error if the filesystem does not belong to this pool.
+    fn activate_filesystem(&mut self, uuid: &FilesystemUuid) -> EngineResult<bool>;
+
+    /// Deactivate the filesystem, removing its device node.
+    /// Returns true if some action was necessary, otherwise false.
+    /// Returns an error if the filesystem does not belong to this pool, and
+    /// a Busy error if the filesystem is mounted.
+    fn deactivate_filesystem(&mut self, uuid: &FilesystemUuid) -> EngineResult<bool>;
+
+    /// Mount the filesystem at mount_point.
+    /// Returns an error if the filesystem does not belong to this pool or
+    /// is not active.
+    fn set_mount_point(&mut self, uuid: &FilesystemUuid, mount_point: &Path) -> EngineResult<()>;
+
     /// Rename this pool.
     fn rename(&mut self, name: &str) -> ();
 
@@ -91,11 +137,83 @@ pub trait Pool: HasName + HasUuid {
     /// or to reserve for some other purpose.
     fn total_physical_used(&self) -> EngineResult<Sectors>;
 
+    /// The number of Sectors in this pool available for user data, once the
+    /// space consumed by metadata and by redundancy is excluded.
+    fn total_usable_size(&self) -> Sectors;
+
+    /// The number of Sectors in the largest contiguous range of
+    /// unallocated space in this pool. No single allocation larger than
+    /// this can be made in one piece.
+    fn largest_free_extent(&self) -> Sectors;
+
+    /// The number of Sectors in this pool consumed by redundancy, i.e., the
+    /// space remaining once metadata is excluded less the usable size.
+    fn redundancy_overhead(&self) -> Sectors;
+
+    /// The number of Sectors that filesystems in this pool may thinly
+    /// allocate. This is the usable size of the pool, scaled
+    /// by the pool's over-provisioning factor and, if the pool deduplicates,
+    /// by its dedup ratio. The over-provisioning factor is not configurable;
+    /// it is fixed by the engine.
+    fn logical_capacity(&self) -> Sectors;
+
     /// Get all the filesystems belonging to this pool.
     fn filesystems(&self) -> Vec<&Filesystem>;
 
     /// Get all the blockdevs that make up this pool.
     fn blockdevs(&self) -> Vec<&BlockDev>;
+
+    /// The redundancy with which this pool stores data.
+    fn redundancy(&self) -> &Redundancy;
+
+    /// The ratio of the data written to this pool to the data actually
+    /// stored, if this pool deduplicates data; None if it does not.
+    fn dedup_ratio(&self) -> Option<f64>;
+
+    /// A hash of this pool's configuration: its redundancy, blockdevs, and
+    /// filesystems and their sizes. Pools configured identically have the
+    /// same hash, regardless of their names or the order in which they were
+    /// built up. The hash is a hex-encoded SHA-256 digest.
+    fn config_hash(&self) -> String;
+
+    /// A human-readable explanation of the state of this pool, naming,
+    /// e.g., any blockdevs which have gone missing.
+    fn status_detail(&self) -> String;
+
+    /// The number of blockdevs in this pool in each status. Statuses
+    /// which no blockdev has are omitted.
+    fn device_status_counts(&self) -> HashMap<DevStatus, usize>;
+
+    /// Whether this pool is omitted from ordinary listings of pools.
+    fn hidden(&self) -> bool;
+
+    /// Set whether this pool is omitted from ordinary listings of pools.
+    /// A hidden pool continues to function normally.
+    fn set_hidden(&mut self, hidden: bool) -> ();
+
+    /// The IO which the device of the filesystem with this name has
+    /// completed. Returns an error if the pool has no such filesystem.
+    fn filesystem_io_stats(&self, name: &str) -> EngineResult<IoStats>;
+
+    /// The names and sizes of the filesystems in this pool of at least
+    /// min_size, sorted by size, largest first if descending is true.
+    fn filesystems_by_size(&self, min_size: Sectors, descending: bool) -> Vec<(String, Sectors)>;
+
+    /// The percentage of this pool's data space in use above which the
+    /// engine extends it.
+    fn data_low_watermark(&self) -> u8;
+
+    /// The percentage of this pool's metadata space in use above which the
+    /// engine warns that it is running out.
+    fn metadata_low_watermark(&self) -> u8;
+
+    /// Set the data low watermark. Returns an error if percent is 0 or
+    /// exceeds 100.
+    fn set_data_low_watermark(&mut self, percent: u8) -> EngineResult<()>;
+
+    /// Set the metadata low watermark. Returns an error if percent is 0 or
+    /// exceeds 100.
+    fn set_metadata_low_watermark(&mut self, percent: u8) -> EngineResult<()>;
 }
 
 pub trait Engine: Debug {
@@ -103,12 +221,22 @@ pub trait Engine: Debug {
     /// Returns the UUID of the newly created pool and the blockdevs the
     /// pool contains.
     /// Returns an error if the redundancy code does not correspond to a
-    /// supported redundancy.
+    /// supported redundancy, or if there are fewer blockdevs than that
+    /// redundancy requires.
+    /// If dedup is true, the pool deduplicates its data; returns an error
+    /// if the engine does not support deduplication.
+    /// If require_uniform_devices is true, returns a DeviceSizeMismatch error
+    /// if the sizes of the blockdevs differ by more than a small tolerance.
+    /// The blockdevs are opened exclusively; returns a Busy error if one is
+    /// held open by some other process, as it is if it already belongs to a
+    /// pool.
     fn create_pool(&mut self,
                    name: &str,
                    blockdev_paths: &[&Path],
                    redundancy: Option<u16>,
-                   force: bool)
+                   force: bool,
+                   dedup: bool,
+                   require_uniform_devices: bool)
                    -> EngineResult<(PoolUuid, Vec<PathBuf>)>;
 
     /// Destroy a pool.
@@ -137,4 +265,58 @@ pub trait Engine: Debug {
 
     /// Get all pools belonging to this engine.
     fn pools(&self) -> Vec<&Pool>;
+
+    /// Get the pools belonging to this engine which are not hidden, or all
+    /// the pools if include_hidden is true.
+    fn list_pools(&self, include_hidden: bool) -> Vec<&Pool>;
+
+    /// The filesystem types that this engine is able to create.
+    fn supported_fs_types(&self) -> Vec<&str>;
+
+    /// Which backend this engine is.
+    fn kind(&self) -> EngineKind;
+
+    /// The directory in which the engine keeps its state. The simulator
+    /// keeps all its state in memory, so the directory it reports is only
+    /// nominal; nothing is read from or written to it.
+    fn data_dir(&self) -> &Path;
+
+    /// The pools most recently destroyed by this engine, earliest first.
+    fn recently_destroyed(&self) -> Vec<DestroyedPoolInfo>;
+
+    /// The names of the filesystems which would lose data if the blockdev
+    /// at path were lost. Returns an error if no pool contains the blockdev.
+    fn filesystems_affected_by_device(&self, path: &Path) -> EngineResult<Vec<String>>;
+
+    /// The kind of data found on the device at path, e.g., "stratis" or
+    /// the type of a filesystem or partition table, or None if the device
+    /// appears to be empty and so may be used in a pool without force.
+    fn device_signature(&self, path: &Path) -> EngineResult<Option<String>>;
+
+    /// The smallest size each of device_count devices must have for a pool
+    /// created from them with the given redundancy to provide volume_size
+    /// of usable space, accounting for the space the engine sets aside for
+    /// its metadata.
+    fn min_device_size_for(&self,
+                           volume_size: Sectors,
+                           redundancy: &Redundancy,
+                           device_count: usize)
+                           -> EngineResult<Sectors>;
+
+    /// Make the pool named by spec conform to it, creating the pool if
+    /// there is none of that name. Returns a description of each change
+    /// made, which is empty if the pool already conformed. Devices are
+    /// compared by the paths they resolve to, so the spec may name them by
+    /// any link.
+    /// Returns an error, having made no change to an existing pool, if the
+    /// pool's redundancy differs from the spec's, if the pool has blockdevs
+    /// which the spec does not list, or if it has filesystems which the
+    /// spec does not name and the spec does not allow them to be destroyed.
+    /// The changes are not atomic: if one fails, those already made are
+    /// kept, and the error's message lists them.
+    fn apply_pool_spec(&mut self, spec: &PoolSpec) -> EngineResult<Vec<String>>;
+
+    /// Metrics describing this engine's pools, in the Prometheus text
+    /// exposition format.
+    fn metrics_prometheus(&self) -> String;
 }
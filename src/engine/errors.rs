@@ -19,8 +19,11 @@ pub enum ErrorEnum {
 
     AlreadyExists,
     Busy,
+    DeviceSizeMismatch,
+    InsufficientSpace,
     Invalid,
     NotFound,
+    NotRedundant,
 }
 
 #[derive(Debug)]
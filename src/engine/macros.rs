@@ -19,6 +19,17 @@ macro_rules! calculate_redundancy {
     }
 }
 
+macro_rules! check_redundancy {
+    ( $s:ident; $require_redundancy:ident ) => {
+        if $require_redundancy && !$s.redundancy.is_redundant() {
+            let message = format!("pool {} has redundancy {}, which is not redundant",
+                                  $s.name,
+                                  $s.redundancy);
+            return Err(EngineError::Engine(ErrorEnum::NotRedundant, message));
+        }
+    }
+}
+
 macro_rules! destroy_pool {
     ( $s:ident; $uuid: ident) => {
         if let Some(ref pool) = $s.pools.get_by_uuid($uuid) {
@@ -29,9 +40,10 @@ macro_rules! destroy_pool {
         } else {
             return Ok(false);
         }
-        $s.pools.remove_by_uuid($uuid)
-             .expect("Must succeed since $s.pool.get_by_uuid() returned a value")
-             .destroy()?;
+        let pool = $s.pools.remove_by_uuid($uuid)
+             .expect("Must succeed since $s.pool.get_by_uuid() returned a value");
+        record_destroyed_pool(&mut $s.destroyed, &pool);
+        pool.destroy()?;
         Ok(true)
     }
 }
@@ -16,12 +16,20 @@ pub use self::errors::ErrorEnum;
 pub use self::sim_engine::SimEngine;
 pub use self::strat_engine::StratEngine;
 
+pub use self::types::DestroyedPoolInfo;
+pub use self::types::DevStatus;
 pub use self::types::DevUuid;
+pub use self::types::EngineKind;
 pub use self::types::FilesystemUuid;
+pub use self::types::IoStats;
+pub use self::types::OpenMode;
+pub use self::types::PoolSpec;
 pub use self::types::PoolUuid;
 pub use self::types::Redundancy;
 pub use self::types::RenameAction;
 
+pub use self::util::{SUPPORTED_FS_TYPES, validate_fs_type};
+
 #[macro_use]
 mod macros;
 
@@ -34,3 +42,4 @@ mod errors;
 mod sim_engine;
 mod structures;
 pub mod types;
+mod util;
@@ -3,23 +3,79 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use std::cell::RefCell;
+use std::cmp::min;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::path::PathBuf;
 use std::rc::Rc;
 
 use uuid::Uuid;
 
+use devicemapper::{IEC, Sectors};
+
 use super::super::engine::{BlockDev, HasUuid};
-use super::super::types::DevUuid;
+use super::super::errors::{EngineError, EngineResult, ErrorEnum};
+use super::super::types::{DevStatus, DevUuid};
 
 use super::randomization::Randomizer;
 
+/// The size of a simulated device which has not been given another.
+const SIM_DEV_SIZE: Sectors = Sectors(IEC::Gi);
+
+#[derive(Debug, Default)]
+/// The devices known to a simulated engine, shared among its pools.
+pub struct SimDevices {
+    /// The sizes of those devices which are not SIM_DEV_SIZE.
+    sizes: HashMap<PathBuf, Sectors>,
+    /// The devices which belong to some pool, and so are held open.
+    held: HashSet<PathBuf>,
+}
+
+impl SimDevices {
+    /// The size of the device at devnode.
+    pub fn size(&self, devnode: &Path) -> Sectors {
+        self.sizes.get(devnode).cloned().unwrap_or(SIM_DEV_SIZE)
+    }
+
+    /// Make the device at devnode have the given size.
+    pub fn set_size(&mut self, devnode: &Path, size: Sectors) -> () {
+        self.sizes.insert(devnode.to_path_buf(), size);
+    }
+
+    /// Verify that none of the devices at devnodes is held, as opening them
+    /// exclusively would; returns a Busy error if one is.
+    pub fn check_free(&self, devnodes: &[&Path]) -> EngineResult<()> {
+        if let Some(devnode) = devnodes.iter().find(|d| self.held.contains(**d)) {
+            let err_msg = format!("device {} is in use by another process",
+                                  devnode.display());
+            return Err(EngineError::Engine(ErrorEnum::Busy, err_msg));
+        }
+        Ok(())
+    }
+
+    /// Hold the devices at devnodes, which now belong to some pool.
+    pub fn hold(&mut self, devnodes: &[&Path]) -> () {
+        self.held
+            .extend(devnodes.iter().map(|d| d.to_path_buf()));
+    }
+
+    /// Release the device at devnode, which no longer belongs to any pool.
+    pub fn release(&mut self, devnode: &Path) -> () {
+        self.held.remove(devnode);
+    }
+}
+
 #[derive(Debug)]
 /// A simulated device.
 pub struct SimDev {
     pub devnode: PathBuf,
     rdm: Rc<RefCell<Randomizer>>,
     uuid: Uuid,
+    size: Sectors,
+    /// The ranges allocated on this device, as (start, length), in order.
+    used: Vec<(Sectors, Sectors)>,
+    evacuated: bool,
+    status: DevStatus,
 }
 
 impl BlockDev for SimDev {
@@ -35,12 +91,129 @@ impl HasUuid for SimDev {
 }
 
 impl SimDev {
-    /// Generates a new device from any devnode.
-    pub fn new(rdm: Rc<RefCell<Randomizer>>, devnode: &Path) -> SimDev {
+    /// Generates a new device of the given size from any devnode.
+    pub fn new(rdm: Rc<RefCell<Randomizer>>, devnode: &Path, size: Sectors) -> SimDev {
         SimDev {
             devnode: devnode.to_owned(),
             rdm: rdm,
             uuid: Uuid::new_v4(),
+            size: size,
+            used: Vec::new(),
+            evacuated: false,
+            status: DevStatus::Online,
         }
     }
+
+    /// The size of this device.
+    pub fn size(&self) -> Sectors {
+        self.size
+    }
+
+    /// The number of Sectors allocated on this device.
+    pub fn allocated(&self) -> Sectors {
+        self.used.iter().map(|&(_, len)| len).sum()
+    }
+
+    /// The ranges allocated on this device, as (start, length), in order.
+    pub fn used_ranges(&self) -> Vec<(Sectors, Sectors)> {
+        self.used.clone()
+    }
+
+    /// The number of Sectors on this device available for allocation.
+    /// An evacuated device has no space available.
+    pub fn avail_space(&self) -> Sectors {
+        if self.evacuated {
+            Sectors(0)
+        } else {
+            self.size - self.allocated()
+        }
+    }
+
+    /// The ranges of this device available for allocation, as (start,
+    /// length), in order.
+    fn free_ranges(&self) -> Vec<(Sectors, Sectors)> {
+        if self.evacuated {
+            return Vec::new();
+        }
+        let mut free = Vec::new();
+        let mut next = Sectors(0);
+        for &(start, len) in &self.used {
+            if start > next {
+                free.push((next, start - next));
+            }
+            next = start + len;
+        }
+        if self.size > next {
+            free.push((next, self.size - next));
+        }
+        free
+    }
+
+    /// The length of the longest range of this device available for
+    /// allocation.
+    pub fn largest_free_extent(&self) -> Sectors {
+        self.free_ranges()
+            .iter()
+            .map(|&(_, len)| len)
+            .max()
+            .unwrap_or(Sectors(0))
+    }
+
+    /// Allocate size Sectors on this device, taking the free ranges in
+    /// order, and return the ranges allocated.
+    /// Precondition: size <= self.avail_space()
+    pub fn alloc_space(&mut self, size: Sectors) -> Vec<(Sectors, Sectors)> {
+        assert!(size <= self.avail_space());
+        let mut allocated = Vec::new();
+        let mut remaining = size;
+        for (start, len) in self.free_ranges() {
+            if remaining == Sectors(0) {
+                break;
+            }
+            let len = min(len, remaining);
+            allocated.push((start, len));
+            remaining = remaining - len;
+        }
+        self.used.extend(allocated.iter().cloned());
+        self.used.sort();
+        allocated
+    }
+
+    /// Release a range which alloc_space allocated.
+    pub fn free_space(&mut self, range: (Sectors, Sectors)) {
+        self.used.retain(|used| *used != range);
+    }
+
+    /// Release all the space allocated on this device and allow no further
+    /// allocations, so that it is ready to be removed.
+    pub fn evacuate(&mut self) {
+        self.used.clear();
+        self.evacuated = true;
+    }
+
+    /// Simulate this device going missing or coming back.
+    #[cfg(test)]
+    pub fn set_present(&mut self, present: bool) {
+        self.status = if present {
+            DevStatus::Online
+        } else {
+            DevStatus::Missing
+        };
+    }
+
+    /// The status of this device in its pool.
+    pub fn status(&self) -> DevStatus {
+        self.status
+    }
+
+    /// Simulate this device failing, or becoming a spare.
+    #[cfg(test)]
+    pub fn set_status(&mut self, status: DevStatus) {
+        self.status = status;
+    }
+
+    /// Whether this device has been evacuated.
+    pub fn evacuated(&self) -> bool {
+        self.evacuated
+    }
 }
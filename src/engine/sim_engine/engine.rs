@@ -3,36 +3,101 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use std::cell::RefCell;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::collections::hash_map::RandomState;
 use std::iter::FromIterator;
 use std::path::Path;
 use std::path::PathBuf;
 use std::rc::Rc;
 
+use devicemapper::Sectors;
+
 use super::super::engine::{Engine, HasName, HasUuid, Pool};
 use super::super::errors::{EngineError, EngineResult, ErrorEnum};
 use super::super::structures::Table;
-use super::super::types::{PoolUuid, Redundancy, RenameAction};
-
+#[cfg(test)]
+use super::super::types::IoStats;
+use super::super::types::{DestroyedPoolInfo, EngineKind, PoolSpec, PoolUuid, Redundancy,
+                          RenameAction};
+use super::super::util::{SUPPORTED_FS_TYPES, apply_pool_spec, filesystems_affected_by_device,
+                         min_device_size, pools_metrics_prometheus, record_destroyed_pool,
+                         validate_device_count, validate_uniform_sizes};
+
+use super::blockdev::SimDevices;
 use super::pool::SimPool;
 use super::randomization::Randomizer;
 
+/// The directory reported by a SimEngine constructed by default. It is
+/// never accessed.
+const DEFAULT_DATA_DIR: &'static str = "/var/lib/stratis-sim";
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct SimEngine {
     pools: Table<SimPool>,
     rdm: Rc<RefCell<Randomizer>>,
+    sim_devices: Rc<RefCell<SimDevices>>,
+    data_dir: PathBuf,
+    destroyed: VecDeque<DestroyedPoolInfo>,
+    /// The signatures of devices not in any pool, which are otherwise
+    /// taken to be empty.
+    signatures: HashMap<PathBuf, String>,
 }
 
-impl SimEngine {}
+impl Default for SimEngine {
+    fn default() -> SimEngine {
+        SimEngine::new(Path::new(DEFAULT_DATA_DIR))
+    }
+}
+
+impl SimEngine {
+    /// Create a SimEngine which reports data_dir as the location of its
+    /// state.
+    pub fn new(data_dir: &Path) -> SimEngine {
+        SimEngine {
+            pools: Table::default(),
+            rdm: Rc::new(RefCell::new(Randomizer::default())),
+            sim_devices: Rc::new(RefCell::new(SimDevices::default())),
+            data_dir: data_dir.to_path_buf(),
+            destroyed: VecDeque::new(),
+            signatures: HashMap::new(),
+        }
+    }
+
+    /// Record that the device at path holds data of the kind signature.
+    pub fn set_device_signature(&mut self, path: &Path, signature: &str) -> () {
+        self.signatures
+            .insert(path.to_path_buf(), signature.to_owned());
+    }
+
+    /// Make the device at path have the given size when it is added to a
+    /// pool.
+    pub fn set_device_size(&mut self, path: &Path, size: Sectors) -> () {
+        self.sim_devices.borrow_mut().set_size(path, size);
+    }
+
+    /// Make the filesystem name in the pool with pool_uuid report io_stats.
+    #[cfg(test)]
+    pub fn set_filesystem_io_stats(&mut self,
+                                   pool_uuid: &PoolUuid,
+                                   name: &str,
+                                   io_stats: IoStats)
+                                   -> () {
+        self.pools
+            .get_mut_by_uuid(pool_uuid)
+            .and_then(|pool| pool.filesystems.get_mut_by_name(name))
+            .expect("filesystem must exist")
+            .set_io_stats(io_stats);
+    }
+}
 
 impl Engine for SimEngine {
     fn create_pool(&mut self,
                    name: &str,
                    blockdev_paths: &[&Path],
                    redundancy: Option<u16>,
-                   _force: bool)
+                   _force: bool,
+                   dedup: bool,
+                   require_uniform_devices: bool)
                    -> EngineResult<(PoolUuid, Vec<PathBuf>)> {
 
         let redundancy = calculate_redundancy!(redundancy);
@@ -41,18 +106,39 @@ impl Engine for SimEngine {
             return Err(EngineError::Engine(ErrorEnum::AlreadyExists, name.into()));
         }
 
+        // Devices are opened exclusively on pool creation; a device which
+        // already belongs to some pool is held and so can not be opened.
+        self.sim_devices.borrow().check_free(blockdev_paths)?;
+
         let device_set: HashSet<_, RandomState> = HashSet::from_iter(blockdev_paths);
         let devices = device_set
             .into_iter()
             .map(|x| *x)
             .collect::<Vec<&Path>>();
 
-        let pool = SimPool::new(Rc::clone(&self.rdm), name, &devices, redundancy);
+        validate_device_count(&redundancy, devices.len())?;
+
+        let pool = SimPool::new(Rc::clone(&self.rdm),
+                                Rc::clone(&self.sim_devices),
+                                name,
+                                &devices,
+                                redundancy,
+                                dedup);
+
+        if require_uniform_devices {
+            let sizes = pool.block_devs
+                .values()
+                .map(|bd| bd.size())
+                .collect::<Vec<_>>();
+            validate_uniform_sizes(&sizes)?;
+        }
 
         if self.rdm.borrow_mut().throw_die() {
             return Err(EngineError::Engine(ErrorEnum::Error, "X".into()));
         }
 
+        self.sim_devices.borrow_mut().hold(&devices);
+
         let bdev_paths = pool.block_devs
             .values()
             .map(|p| p.devnode.clone())
@@ -100,16 +186,75 @@ impl Engine for SimEngine {
     fn pools(&self) -> Vec<&Pool> {
         self.pools.into_iter().map(|x| x as &Pool).collect()
     }
+
+    fn list_pools(&self, include_hidden: bool) -> Vec<&Pool> {
+        self.pools
+            .into_iter()
+            .map(|x| x as &Pool)
+            .filter(|x| include_hidden || !x.hidden())
+            .collect()
+    }
+
+    fn supported_fs_types(&self) -> Vec<&str> {
+        SUPPORTED_FS_TYPES.to_vec()
+    }
+
+    fn kind(&self) -> EngineKind {
+        EngineKind::Sim
+    }
+
+    fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    fn recently_destroyed(&self) -> Vec<DestroyedPoolInfo> {
+        self.destroyed.iter().cloned().collect()
+    }
+
+    fn filesystems_affected_by_device(&self, path: &Path) -> EngineResult<Vec<String>> {
+        filesystems_affected_by_device(&self.pools(), path)
+    }
+
+    fn device_signature(&self, path: &Path) -> EngineResult<Option<String>> {
+        if self.pools
+               .into_iter()
+               .any(|p| p.block_devs.contains_key(path)) {
+            return Ok(Some("stratis".into()));
+        }
+        Ok(self.signatures.get(path).cloned())
+    }
+
+    fn min_device_size_for(&self,
+                           volume_size: Sectors,
+                           redundancy: &Redundancy,
+                           device_count: usize)
+                           -> EngineResult<Sectors> {
+        min_device_size(volume_size, redundancy, device_count, Sectors(0), Sectors(0))
+    }
+
+    fn apply_pool_spec(&mut self, spec: &PoolSpec) -> EngineResult<Vec<String>> {
+        apply_pool_spec(self, spec)
+    }
+
+    fn metrics_prometheus(&self) -> String {
+        pools_metrics_prometheus(&self.pools())
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
     use std;
+    use std::fs::File;
+    use std::os::unix::fs::symlink;
     use std::path::Path;
 
+    use tempdir::TempDir;
+
     use uuid::Uuid;
 
+    use devicemapper::{IEC, Sectors};
+
     use quickcheck::QuickCheck;
 
     use super::SimEngine;
@@ -117,7 +262,10 @@ mod tests {
     use engine::Engine;
     use engine::EngineError;
     use engine::ErrorEnum;
+    use engine::PoolSpec;
+    use engine::Redundancy;
     use engine::RenameAction;
+    use engine::validate_fs_type;
 
     #[test]
     fn prop_configure_simulator_runs() {
@@ -152,7 +300,7 @@ mod tests {
     /// Destroying an empty pool should succeed.
     fn destroy_empty_pool() {
         let mut engine = SimEngine::default();
-        let (uuid, _) = engine.create_pool("name", &[], None, false).unwrap();
+        let (uuid, _) = engine.create_pool("name", &[], None, false, false, false).unwrap();
         assert!(engine.destroy_pool(&uuid).is_ok());
     }
 
@@ -161,7 +309,7 @@ mod tests {
     fn destroy_pool_w_devices() {
         let mut engine = SimEngine::default();
         let (uuid, _) = engine
-            .create_pool("name", &[Path::new("/s/d")], None, false)
+            .create_pool("name", &[Path::new("/s/d")], None, false, false, false)
             .unwrap();
         assert!(engine.destroy_pool(&uuid).is_ok());
     }
@@ -171,11 +319,11 @@ mod tests {
     fn destroy_pool_w_filesystem() {
         let mut engine = SimEngine::default();
         let (uuid, _) = engine
-            .create_pool("name", &[Path::new("/s/d")], None, false)
+            .create_pool("name", &[Path::new("/s/d")], None, false, false, false)
             .unwrap();
         {
             let pool = engine.get_mut_pool(&uuid).unwrap();
-            pool.create_filesystems(&[("test", None)]).unwrap();
+            pool.create_filesystems(&[("test", None)], false).unwrap();
         }
         assert!(engine.destroy_pool(&uuid).is_err());
     }
@@ -186,8 +334,8 @@ mod tests {
     fn create_new_pool_twice() {
         let name = "name";
         let mut engine = SimEngine::default();
-        engine.create_pool(name, &[], None, false).unwrap();
-        assert!(match engine.create_pool(name, &[], None, false) {
+        engine.create_pool(name, &[], None, false, false, false).unwrap();
+        assert!(match engine.create_pool(name, &[], None, false, false, false) {
                     Ok((_, devs)) => devs.is_empty(),
                     Err(_) => false,
                 });
@@ -199,21 +347,46 @@ mod tests {
         let name = "name";
         let mut engine = SimEngine::default();
         engine
-            .create_pool(name, &[Path::new("/s/d")], None, false)
+            .create_pool(name, &[Path::new("/s/d")], None, false, false, false)
             .unwrap();
-        assert!(match engine.create_pool(name, &[], None, false) {
+        assert!(match engine.create_pool(name, &[], None, false, false, false) {
                     Err(EngineError::Engine(ErrorEnum::AlreadyExists, _)) => true,
                     _ => false,
                 });
     }
 
+    #[test]
+    /// Creating a pool with fewer devices than its redundancy requires
+    /// should fail
+    fn create_pool_too_few_devices() {
+        let mut engine = SimEngine::default();
+        assert!(match engine.create_pool("name",
+                                         &[Path::new("/s/a")],
+                                         Some(u16::from(Redundancy::RAID1)),
+                                         false,
+                                         false,
+                                         false) {
+                    Err(EngineError::Engine(ErrorEnum::Invalid, _)) => true,
+                    _ => false,
+                });
+        assert!(match engine.create_pool("name",
+                                         &[Path::new("/s/a"), Path::new("/s/b")],
+                                         Some(u16::from(Redundancy::RAID5)),
+                                         false,
+                                         false,
+                                         false) {
+                    Err(EngineError::Engine(ErrorEnum::Invalid, _)) => true,
+                    _ => false,
+                });
+    }
+
     #[test]
     /// Creating a pool with duplicate devices should succeed
     fn create_pool_duplicate_devices() {
         let path = "/s/d";
         let mut engine = SimEngine::default();
         let devices = vec![Path::new(path), Path::new(path)];
-        assert!(match engine.create_pool("name", &devices, None, false) {
+        assert!(match engine.create_pool("name", &devices, None, false, false, false) {
                     Ok((_, devs)) => devs.len() == 1,
                     _ => false,
                 });
@@ -224,10 +397,185 @@ mod tests {
     fn create_pool_max_u16_raid() {
         let mut engine = SimEngine::default();
         assert!(engine
-                    .create_pool("name", &[], Some(std::u16::MAX), false)
+                    .create_pool("name", &[], Some(std::u16::MAX), false, false, false)
+                    .is_err());
+    }
+
+    #[test]
+    /// Creating a pool with a device held by another pool should fail
+    fn create_pool_busy_device() {
+        let mut engine = SimEngine::default();
+        engine
+            .create_pool("name", &[Path::new("/s/d")], None, false, false, false)
+            .unwrap();
+        assert!(match engine.create_pool("other_name",
+                                         &[Path::new("/s/d")],
+                                         None,
+                                         false,
+                                         false,
+                                         false) {
+                    Err(EngineError::Engine(ErrorEnum::Busy, _)) => true,
+                    _ => false,
+                });
+    }
+
+    #[test]
+    /// Devices of 1 GiB and 2 GiB are rejected when uniform devices are
+    /// required, two devices of 1 GiB are not
+    fn create_pool_uniform_devices() {
+        let mut engine = SimEngine::default();
+        engine.set_device_size(Path::new("/s/a"), Sectors(2 * IEC::Mi));
+        engine.set_device_size(Path::new("/s/b"), Sectors(4 * IEC::Mi));
+        engine.set_device_size(Path::new("/s/c"), Sectors(2 * IEC::Mi));
+        assert!(match engine.create_pool("mixed",
+                                         &[Path::new("/s/a"), Path::new("/s/b")],
+                                         None,
+                                         false,
+                                         false,
+                                         true) {
+                    Err(EngineError::Engine(ErrorEnum::DeviceSizeMismatch, _)) => true,
+                    _ => false,
+                });
+        let (uuid, _) = engine
+            .create_pool("uniform",
+                         &[Path::new("/s/a"), Path::new("/s/c")],
+                         Some(u16::from(Redundancy::RAID1)),
+                         false,
+                         false,
+                         true)
+            .unwrap();
+        let pool = engine.get_pool(&uuid).unwrap();
+        assert_eq!(pool.total_physical_size(), Sectors(4 * IEC::Mi));
+        assert_eq!(pool.total_usable_size(), Sectors(2 * IEC::Mi));
+    }
+
+    #[test]
+    /// The supported filesystem types include xfs and all pass validation
+    fn supported_fs_types_valid() {
+        let engine = SimEngine::default();
+        let fs_types = engine.supported_fs_types();
+        assert!(fs_types.contains(&"xfs"));
+        assert!(fs_types.iter().all(|t| validate_fs_type(t).is_ok()));
+    }
+
+    #[test]
+    /// A SimEngine reports that it is the simulator
+    fn kind_sim() {
+        assert_eq!(format!("{}", SimEngine::default().kind()), "sim");
+    }
+
+    #[test]
+    /// A SimEngine reports the data directory it was constructed with
+    fn data_dir_reported() {
+        let data_dir = Path::new("/tmp/stratis-sim-data");
+        assert_eq!(SimEngine::new(data_dir).data_dir(), data_dir);
+    }
+
+    #[test]
+    /// A hidden pool is listed only when hidden pools are included
+    fn list_pools_hidden() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("hidden", &[], None, false, false, false)
+            .unwrap();
+        engine
+            .create_pool("visible", &[], None, false, false, false)
+            .unwrap();
+        engine.get_mut_pool(&uuid).unwrap().set_hidden(true);
+
+        let names = |include_hidden| {
+            let mut names = engine
+                .list_pools(include_hidden)
+                .iter()
+                .map(|p| p.name().to_owned())
+                .collect::<Vec<_>>();
+            names.sort();
+            names
+        };
+        assert_eq!(names(false), vec!["visible"]);
+        assert_eq!(names(true), vec!["hidden", "visible"]);
+        assert!(engine.get_pool(&uuid).is_some());
+    }
+
+    #[test]
+    /// The metrics include the total size of a pool, labelled by its name
+    fn metrics_prometheus_pool_total() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("name", &[Path::new("/s/d")], None, false, false, false)
+            .unwrap();
+        let metrics = engine.metrics_prometheus();
+        let sample = format!("stratis_pool_total_bytes{{pool=\"name\",uuid=\"{}\"}} ",
+                             uuid.simple());
+        assert!(metrics.lines().any(|l| l.starts_with(&sample)));
+        assert!(metrics.contains("stratis_pools 1"));
+    }
+
+    #[test]
+    /// Losing a device of a non-redundant pool affects all its
+    /// filesystems, losing a device of a RAID1 pool affects none
+    fn filesystems_affected_by_device_redundancy() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("plain",
+                         &[Path::new("/s/a"), Path::new("/s/b")],
+                         None,
+                         false,
+                         false,
+                         false)
+            .unwrap();
+        engine
+            .get_mut_pool(&uuid)
+            .unwrap()
+            .create_filesystems(&[("fs1", None), ("fs2", None)], false)
+            .unwrap();
+        let (uuid, _) = engine
+            .create_pool("mirror",
+                         &[Path::new("/s/c"), Path::new("/s/d")],
+                         Some(u16::from(Redundancy::RAID1)),
+                         false,
+                         false,
+                         false)
+            .unwrap();
+        engine
+            .get_mut_pool(&uuid)
+            .unwrap()
+            .create_filesystems(&[("fs3", None)], false)
+            .unwrap();
+
+        let mut affected = engine
+            .filesystems_affected_by_device(Path::new("/s/a"))
+            .unwrap();
+        affected.sort();
+        assert_eq!(affected, vec!["fs1", "fs2"]);
+        assert!(engine
+                    .filesystems_affected_by_device(Path::new("/s/c"))
+                    .unwrap()
+                    .is_empty());
+        assert!(engine
+                    .filesystems_affected_by_device(Path::new("/s/z"))
                     .is_err());
     }
 
+    #[test]
+    /// A destroyed pool is remembered along with the devices it occupied
+    fn destroyed_pool_recorded() {
+        let mut engine = SimEngine::default();
+        let devices = [Path::new("/s/d"), Path::new("/s/e")];
+        let (uuid, _) = engine
+            .create_pool("name", &devices, None, false, false, false)
+            .unwrap();
+        assert!(engine.recently_destroyed().is_empty());
+        engine.destroy_pool(&uuid).unwrap();
+        let destroyed = engine.recently_destroyed();
+        assert_eq!(destroyed.len(), 1);
+        assert_eq!(destroyed[0].name, "name");
+        assert_eq!(destroyed[0].uuid, uuid);
+        let mut devnodes = destroyed[0].devnodes.clone();
+        devnodes.sort();
+        assert_eq!(devnodes, devices.iter().map(|d| d.to_path_buf()).collect::<Vec<_>>());
+    }
+
     #[test]
     /// Renaming a pool on an empty engine always works
     fn rename_empty() {
@@ -243,7 +591,7 @@ mod tests {
     fn rename_identity() {
         let name = "name";
         let mut engine = SimEngine::default();
-        let (uuid, _) = engine.create_pool(name, &[], None, false).unwrap();
+        let (uuid, _) = engine.create_pool(name, &[], None, false, false, false).unwrap();
         assert!(match engine.rename_pool(&uuid, name) {
                     Ok(RenameAction::Identity) => true,
                     _ => false,
@@ -254,7 +602,7 @@ mod tests {
     /// Renaming a pool to another pool should work if new name not taken
     fn rename_happens() {
         let mut engine = SimEngine::default();
-        let (uuid, _) = engine.create_pool("old_name", &[], None, false).unwrap();
+        let (uuid, _) = engine.create_pool("old_name", &[], None, false, false, false).unwrap();
         assert!(match engine.rename_pool(&uuid, "new_name") {
                     Ok(RenameAction::Renamed) => true,
                     _ => false,
@@ -266,8 +614,8 @@ mod tests {
     fn rename_fails() {
         let new_name = "new_name";
         let mut engine = SimEngine::default();
-        let (uuid, _) = engine.create_pool("old_name", &[], None, false).unwrap();
-        engine.create_pool(new_name, &[], None, false).unwrap();
+        let (uuid, _) = engine.create_pool("old_name", &[], None, false, false, false).unwrap();
+        engine.create_pool(new_name, &[], None, false, false, false).unwrap();
         assert!(match engine.rename_pool(&uuid, new_name) {
                     Err(EngineError::Engine(ErrorEnum::AlreadyExists, _)) => true,
                     _ => false,
@@ -279,11 +627,97 @@ mod tests {
     fn rename_no_op() {
         let new_name = "new_name";
         let mut engine = SimEngine::default();
-        engine.create_pool(new_name, &[], None, false).unwrap();
+        engine.create_pool(new_name, &[], None, false, false, false).unwrap();
         assert!(match engine.rename_pool(&Uuid::new_v4(), new_name) {
                     Ok(RenameAction::NoSource) => true,
                     _ => false,
                 });
     }
 
+    #[test]
+    /// Applying a spec makes changes; applying it again makes none, since
+    /// the pool already conforms.
+    fn apply_pool_spec_converges() {
+        let mut engine = SimEngine::default();
+        let spec = PoolSpec {
+            name: "name".into(),
+            devices: vec!["/s/a".into(), "/s/b".into()],
+            redundancy: Some(u16::from(Redundancy::RAID1)),
+            filesystems: vec!["fs".into()],
+            allow_destroy: false,
+        };
+        assert!(!engine.apply_pool_spec(&spec).unwrap().is_empty());
+        assert!(engine.apply_pool_spec(&spec).unwrap().is_empty());
+
+        let spec = PoolSpec {
+            filesystems: vec![],
+            ..spec
+        };
+        assert!(match engine.apply_pool_spec(&spec) {
+                    Err(EngineError::Engine(ErrorEnum::Invalid, _)) => true,
+                    _ => false,
+                });
+    }
+
+    #[test]
+    /// A spec which names a pool's device by a link to it conforms to the
+    /// pool, rather than removing the device and adding the link.
+    fn apply_pool_spec_device_link() {
+        let tmp_dir = TempDir::new("stratis_sim_spec_").unwrap();
+        let device = tmp_dir.path().join("device");
+        let link = tmp_dir.path().join("link");
+        File::create(&device).unwrap();
+        symlink(&device, &link).unwrap();
+
+        let mut engine = SimEngine::default();
+        engine
+            .create_pool("name", &[device.as_path()], None, false, false, false)
+            .unwrap();
+        let spec = PoolSpec {
+            name: "name".into(),
+            devices: vec![link],
+            redundancy: None,
+            filesystems: vec![],
+            allow_destroy: false,
+        };
+        assert!(engine.apply_pool_spec(&spec).unwrap().is_empty());
+    }
+
+    #[test]
+    /// If a change fails, the changes already made are kept, and the error
+    /// lists them.
+    fn apply_pool_spec_partial() {
+        let mut engine = SimEngine::default();
+        engine.set_device_size(Path::new("/s/a"), Sectors(IEC::Ki));
+        let spec = PoolSpec {
+            name: "name".into(),
+            devices: vec!["/s/a".into()],
+            redundancy: None,
+            filesystems: vec!["fs".into()],
+            allow_destroy: false,
+        };
+        assert!(match engine.apply_pool_spec(&spec) {
+                    Err(EngineError::Engine(ErrorEnum::InsufficientSpace, msg)) => {
+                        msg.contains("created pool name")
+                    }
+                    _ => false,
+                });
+        assert!(engine.pools().iter().any(|p| p.name() == "name"));
+    }
+
+    #[test]
+    /// A device with a signature is not empty, nor is one in a pool; any
+    /// other device is.
+    fn device_signature_fixtured() {
+        let mut engine = SimEngine::default();
+        engine.set_device_signature(Path::new("/s/ext4"), "ext4");
+        engine
+            .create_pool("name", &[Path::new("/s/a")], None, false, false, false)
+            .unwrap();
+        assert_eq!(engine.device_signature(Path::new("/s/ext4")).unwrap(),
+                   Some("ext4".into()));
+        assert_eq!(engine.device_signature(Path::new("/s/a")).unwrap(),
+                   Some("stratis".into()));
+        assert_eq!(engine.device_signature(Path::new("/s/b")).unwrap(), None);
+    }
 }
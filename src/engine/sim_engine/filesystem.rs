@@ -2,22 +2,33 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use devicemapper::Sectors;
 
 use super::super::engine::{HasName, HasUuid, Filesystem};
-use super::super::types::FilesystemUuid;
+use super::super::errors::{EngineError, EngineResult, ErrorEnum};
+use super::super::types::{FilesystemUuid, IoStats};
 
 #[derive(Debug)]
 pub struct SimFilesystem {
     fs_id: FilesystemUuid,
     name: String,
+    size: Sectors,
+    active: bool,
+    mount_point: Option<PathBuf>,
+    io_stats: IoStats,
 }
 
 impl SimFilesystem {
-    pub fn new(fs_id: FilesystemUuid, name: &str) -> SimFilesystem {
+    pub fn new(fs_id: FilesystemUuid, name: &str, size: Sectors) -> SimFilesystem {
         SimFilesystem {
             fs_id: fs_id,
             name: name.to_owned(),
+            size: size,
+            active: true,
+            mount_point: None,
+            io_stats: IoStats::default(),
         }
     }
 
@@ -25,12 +36,59 @@ impl SimFilesystem {
     pub fn rename(&mut self, name: &str) {
         self.name = name.to_owned();
     }
+
+    /// Activate the filesystem. Return true if it was inactive.
+    pub fn activate(&mut self) -> bool {
+        let action = !self.active;
+        self.active = true;
+        action
+    }
+
+    /// Deactivate the filesystem. Return true if it was active.
+    pub fn deactivate(&mut self) -> EngineResult<bool> {
+        if self.mount_point.is_some() {
+            return Err(EngineError::Engine(ErrorEnum::Busy,
+                                           format!("filesystem {} is mounted", self.name)));
+        }
+        let action = self.active;
+        self.active = false;
+        Ok(action)
+    }
+
+    /// Record the filesystem as mounted at mount_point.
+    pub fn set_mount_point(&mut self, mount_point: &Path) -> EngineResult<()> {
+        if !self.active {
+            return Err(EngineError::Engine(ErrorEnum::Invalid,
+                                           format!("filesystem {} is not active", self.name)));
+        }
+        self.mount_point = Some(mount_point.to_path_buf());
+        Ok(())
+    }
+
+    /// The IO which the filesystem has been set to report.
+    pub fn io_stats(&self) -> IoStats {
+        self.io_stats
+    }
+
+    /// Set the IO which the filesystem reports.
+    #[cfg(test)]
+    pub fn set_io_stats(&mut self, io_stats: IoStats) -> () {
+        self.io_stats = io_stats;
+    }
 }
 
 impl Filesystem for SimFilesystem {
     fn devnode(&self) -> PathBuf {
         ["/dev/stratis", &self.name].into_iter().collect()
     }
+
+    fn is_active(&self) -> bool {
+        self.active
+    }
+
+    fn size(&self) -> Sectors {
+        self.size
+    }
 }
 
 impl HasName for SimFilesystem {
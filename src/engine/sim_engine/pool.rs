@@ -3,6 +3,7 @@
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use std::cell::RefCell;
+use std::cmp::min;
 use std::collections::{HashMap, HashSet};
 use std::collections::hash_map::RandomState;
 use std::iter::FromIterator;
@@ -18,69 +19,266 @@ use devicemapper::{IEC, Sectors};
 use super::super::engine::{Filesystem, BlockDev, HasName, HasUuid, Pool};
 use super::super::errors::{EngineError, EngineResult, ErrorEnum};
 use super::super::structures::Table;
-use super::super::types::{FilesystemUuid, PoolUuid, RenameAction, Redundancy};
+use super::super::types::{DevStatus, FilesystemUuid, IoStats, PoolUuid, RenameAction,
+                          Redundancy};
+use super::super::util::{DEFAULT_LOW_WATERMARK, above_watermark, filesystems_by_size,
+                         pool_config_hash, pool_device_status_counts, pool_status_detail,
+                         validate_filesystem_size, validate_low_watermark, watermark_warning};
 
-use super::blockdev::SimDev;
+use super::blockdev::{SimDev, SimDevices};
 use super::filesystem::SimFilesystem;
 use super::randomization::Randomizer;
 
+/// The dedup ratio reported by every simulated pool that deduplicates.
+const SIM_DEDUP_RATIO: f64 = 1.5;
+
+/// The size of a simulated filesystem created without a size, the same as
+/// that of a real one.
+const DEFAULT_FILESYSTEM_SIZE: Sectors = Sectors(2 * IEC::Gi);
+
+/// The space a simulated filesystem occupies on its pool's blockdevs.
+const SIM_FS_ALLOCATION: Sectors = Sectors(128 * IEC::Ki);
+
+/// The over-provisioning factor of a newly created simulated pool.
+const DEFAULT_OVERPROVISION_FACTOR: u64 = 1;
+
+/// The size of a simulated pool's metadata space.
+const SIM_METADATA_SIZE: Sectors = Sectors(16 * IEC::Ki);
+
+/// The metadata space a simulated filesystem uses.
+const SIM_FS_METADATA: Sectors = Sectors(IEC::Ki);
+
+/// A warning given by a simulated pool's check.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PoolWarning {
+    /// Data usage has risen above the data low watermark.
+    DataLowSpace,
+    /// Metadata usage has risen above the metadata low watermark.
+    MetadataLowSpace,
+}
+
 #[derive(Debug)]
 pub struct SimPool {
     name: String,
     pool_uuid: PoolUuid,
     pub block_devs: HashMap<PathBuf, SimDev>,
     pub filesystems: Table<SimFilesystem>,
+    /// The ranges of its blockdevs which each filesystem occupies.
+    allocations: HashMap<FilesystemUuid, Vec<(PathBuf, (Sectors, Sectors))>>,
     redundancy: Redundancy,
+    dedup_ratio: Option<f64>,
+    overprovision_factor: u64,
+    hidden: bool,
+    data_low_watermark: u8,
+    metadata_low_watermark: u8,
+    /// Whether a warning has been logged that data usage is above its
+    /// watermark, since it last was not.
+    data_warned: bool,
+    /// Likewise, for metadata usage.
+    metadata_warned: bool,
     rdm: Rc<RefCell<Randomizer>>,
+    sim_devices: Rc<RefCell<SimDevices>>,
 }
 
 impl SimPool {
     pub fn new(rdm: Rc<RefCell<Randomizer>>,
+               sim_devices: Rc<RefCell<SimDevices>>,
                name: &str,
                paths: &[&Path],
-               redundancy: Redundancy)
+               redundancy: Redundancy,
+               dedup: bool)
                -> SimPool {
 
         let devices: HashSet<_, RandomState> = HashSet::from_iter(paths);
         let device_pairs = devices
             .iter()
-            .map(|p| (p.to_path_buf(), SimDev::new(Rc::clone(&rdm), p)));
+            .map(|p| {
+                     let size = sim_devices.borrow().size(p);
+                     (p.to_path_buf(), SimDev::new(Rc::clone(&rdm), p, size))
+                 })
+            .collect::<Vec<_>>();
         SimPool {
             name: name.to_owned(),
             pool_uuid: Uuid::new_v4(),
             block_devs: HashMap::from_iter(device_pairs),
             filesystems: Table::default(),
+            allocations: HashMap::new(),
             redundancy: redundancy,
+            dedup_ratio: if dedup { Some(SIM_DEDUP_RATIO) } else { None },
+            overprovision_factor: DEFAULT_OVERPROVISION_FACTOR,
+            hidden: false,
+            data_low_watermark: DEFAULT_LOW_WATERMARK,
+            metadata_low_watermark: DEFAULT_LOW_WATERMARK,
+            data_warned: false,
+            metadata_warned: false,
             rdm: Rc::clone(&rdm),
+            sim_devices: sim_devices,
         }
     }
 
-    pub fn check(&mut self) -> EngineResult<()> {
-        Ok(())
+    /// Check the pool's data and metadata usage against their watermarks.
+    /// Returns the warnings given, each of which is given only once for
+    /// as long as usage stays above its watermark.
+    pub fn check(&mut self) -> EngineResult<Vec<PoolWarning>> {
+        let mut warnings = Vec::new();
+
+        let allocated: Sectors = self.block_devs.values().map(|bd| bd.allocated()).sum();
+        let above = above_watermark(*allocated,
+                                    *self.total_physical_size(),
+                                    self.data_low_watermark);
+        let (warn, warned) = watermark_warning(above, self.data_warned);
+        self.data_warned = warned;
+        if warn {
+            warn!("pool {} data usage is above its low watermark", self.name);
+            warnings.push(PoolWarning::DataLowSpace);
+        }
+
+        let meta_above = above_watermark(*self.metadata_used(),
+                                         *SIM_METADATA_SIZE,
+                                         self.metadata_low_watermark);
+        let (warn, warned) = watermark_warning(meta_above, self.metadata_warned);
+        self.metadata_warned = warned;
+        if warn {
+            warn!("pool {} metadata usage is above its low watermark", self.name);
+            warnings.push(PoolWarning::MetadataLowSpace);
+        }
+
+        Ok(warnings)
+    }
+
+    /// The metadata space used by the pool's filesystems.
+    fn metadata_used(&self) -> Sectors {
+        SIM_FS_METADATA * self.filesystems.len() as u64
     }
 
     pub fn has_filesystems(&self) -> bool {
         !self.filesystems.is_empty()
     }
+
+    /// The space available on the blockdevs other than exclude.
+    fn avail_space(&self, exclude: Option<&Path>) -> Sectors {
+        self.block_devs
+            .iter()
+            .filter(|&(p, _)| Some(p.as_path()) != exclude)
+            .map(|(_, bd)| bd.avail_space())
+            .sum()
+    }
+
+    /// Allocate size Sectors on the blockdevs other than exclude, taking
+    /// them in order of devnode, and return the ranges allocated.
+    /// Precondition: size <= self.avail_space(exclude)
+    fn alloc_space(&mut self,
+                   size: Sectors,
+                   exclude: Option<&Path>)
+                   -> Vec<(PathBuf, (Sectors, Sectors))> {
+        let mut devnodes = self.block_devs
+            .keys()
+            .filter(|p| Some(p.as_path()) != exclude)
+            .cloned()
+            .collect::<Vec<_>>();
+        devnodes.sort();
+
+        let mut allocated = Vec::new();
+        let mut remaining = size;
+        for devnode in devnodes {
+            let bd = self.block_devs
+                .get_mut(&devnode)
+                .expect("devnode is a key of self.block_devs");
+            let len = min(remaining, bd.avail_space());
+            allocated.extend(bd.alloc_space(len)
+                                 .into_iter()
+                                 .map(|range| (devnode.clone(), range)));
+            remaining = remaining - len;
+        }
+        assert_eq!(remaining, Sectors(0));
+        allocated
+    }
+
+    /// The sizes of the blockdevs which have not been evacuated.
+    fn blockdev_sizes(&self) -> Vec<Sectors> {
+        self.block_devs
+            .values()
+            .filter(|bd| !bd.evacuated())
+            .map(|bd| bd.size())
+            .collect()
+    }
+
+    /// Set the factor by which this pool's logical capacity exceeds its
+    /// physical capacity. Only tests may change it; otherwise it is fixed,
+    /// as it is for pools of the strat engine.
+    #[cfg(test)]
+    pub fn set_overprovision_factor(&mut self, factor: u64) {
+        self.overprovision_factor = factor;
+    }
 }
 
 impl Pool for SimPool {
     fn add_blockdevs(&mut self, paths: &[&Path], _force: bool) -> EngineResult<Vec<PathBuf>> {
+        self.sim_devices.borrow().check_free(paths)?;
+
         let rdm = Rc::clone(&self.rdm);
         let devices: HashSet<_, RandomState> = HashSet::from_iter(paths);
         let device_pairs = devices
             .iter()
-            .map(|p| (p.to_path_buf(), SimDev::new(Rc::clone(&rdm), p)));
+            .map(|p| {
+                     let size = self.sim_devices.borrow().size(p);
+                     (p.to_path_buf(), SimDev::new(Rc::clone(&rdm), p, size))
+                 })
+            .collect::<Vec<_>>();
         self.block_devs.extend(device_pairs);
+        self.sim_devices.borrow_mut().hold(paths);
         Ok(devices.iter().map(|d| d.to_path_buf()).collect())
     }
 
+    fn evacuate_blockdev(&mut self, path: &Path) -> EngineResult<()> {
+        let ranges = match self.block_devs.get(path) {
+            Some(bd) => bd.used_ranges(),
+            None => {
+                let err_msg = format!("blockdev {} does not belong to pool", path.display());
+                return Err(EngineError::Engine(ErrorEnum::NotFound, err_msg));
+            }
+        };
+
+        let allocated: Sectors = ranges.iter().map(|&(_, len)| len).sum();
+        if self.avail_space(Some(path)) < allocated {
+            let err_msg = format!("insufficient space on remaining blockdevs to evacuate {}",
+                                  path.display());
+            return Err(EngineError::Engine(ErrorEnum::InsufficientSpace, err_msg));
+        }
+
+        // Move each range elsewhere, keeping track of where the data of the
+        // filesystem which occupies it, if any, now is.
+        for range in ranges {
+            let moved = self.alloc_space(range.1, Some(path));
+            for extents in self.allocations.values_mut() {
+                if let Some(index) = extents
+                       .iter()
+                       .position(|&(ref p, r)| p == path && r == range) {
+                    extents.remove(index);
+                    extents.extend(moved.iter().cloned());
+                }
+            }
+        }
+
+        self.block_devs
+            .get_mut(path)
+            .expect("Must succeed since self.block_devs.get() returned a value")
+            .evacuate();
+        Ok(())
+    }
+
     fn destroy_filesystems<'a, 'b>(&'a mut self,
                                    fs_uuids: &[&'b FilesystemUuid])
                                    -> EngineResult<Vec<&'b FilesystemUuid>> {
         let mut removed = Vec::new();
         for uuid in fs_uuids {
             if self.filesystems.remove_by_uuid(uuid).is_some() {
+                for (devnode, range) in self.allocations.remove(*uuid).unwrap_or_default() {
+                    self.block_devs
+                        .get_mut(&devnode)
+                        .expect("filesystems occupy only the pool's blockdevs")
+                        .free_space(range);
+                }
                 removed.push(*uuid);
             }
         }
@@ -88,13 +286,22 @@ impl Pool for SimPool {
     }
 
     fn destroy(self) -> EngineResult<()> {
-        // Nothing to do here.
+        for devnode in self.block_devs.keys() {
+            self.sim_devices.borrow_mut().release(devnode);
+        }
         Ok(())
     }
 
     fn create_filesystems<'a, 'b>(&'a mut self,
-                                  specs: &[(&'b str, Option<Sectors>)])
+                                  specs: &[(&'b str, Option<Sectors>)],
+                                  require_redundancy: bool)
                                   -> EngineResult<Vec<(&'b str, FilesystemUuid)>> {
+        check_redundancy!(self; require_redundancy);
+
+        for size in specs.iter().filter_map(|&(_, size)| size) {
+            validate_filesystem_size(size)?;
+        }
+
         let names: HashMap<_, _> = HashMap::from_iter(specs.iter().map(|&tup| (tup.0, tup.1)));
         for name in names.keys() {
             if self.filesystems.contains_name(name) {
@@ -102,10 +309,21 @@ impl Pool for SimPool {
             }
         }
 
+        let needed = SIM_FS_ALLOCATION * names.len() as u64;
+        if self.avail_space(None) < needed {
+            let err_msg = format!("pool {} has too little space for {} filesystems",
+                                  self.name,
+                                  names.len());
+            return Err(EngineError::Engine(ErrorEnum::InsufficientSpace, err_msg));
+        }
+
         let mut result = Vec::new();
-        for name in names.keys() {
+        for (name, size) in &names {
             let uuid = Uuid::new_v4();
-            let new_filesystem = SimFilesystem::new(uuid, name);
+            let extents = self.alloc_space(SIM_FS_ALLOCATION, None);
+            self.allocations.insert(uuid, extents);
+            let new_filesystem =
+                SimFilesystem::new(uuid, name, size.unwrap_or(DEFAULT_FILESYSTEM_SIZE));
             self.filesystems.insert(new_filesystem);
             result.push((*name, uuid));
         }
@@ -129,6 +347,56 @@ impl Pool for SimPool {
         Ok(RenameAction::Renamed)
     }
 
+    fn swap_filesystem_names(&mut self,
+                             uuid_a: &FilesystemUuid,
+                             uuid_b: &FilesystemUuid)
+                             -> EngineResult<()> {
+        for uuid in &[uuid_a, uuid_b] {
+            if self.filesystems.get_by_uuid(uuid).is_none() {
+                return Err(EngineError::Engine(ErrorEnum::NotFound, uuid.simple().to_string()));
+            }
+        }
+        if uuid_a == uuid_b {
+            return Ok(());
+        }
+
+        let mut filesystem_a = self.filesystems
+            .remove_by_uuid(uuid_a)
+            .expect("Must succeed since self.filesystems.get_by_uuid() returned a value");
+        let mut filesystem_b = self.filesystems
+            .remove_by_uuid(uuid_b)
+            .expect("Must succeed since self.filesystems.get_by_uuid() returned a value");
+
+        let name_a = filesystem_a.name().to_owned();
+        filesystem_a.rename(filesystem_b.name());
+        filesystem_b.rename(&name_a);
+
+        self.filesystems.insert(filesystem_a);
+        self.filesystems.insert(filesystem_b);
+        Ok(())
+    }
+
+    fn activate_filesystem(&mut self, uuid: &FilesystemUuid) -> EngineResult<bool> {
+        match self.filesystems.get_mut_by_uuid(uuid) {
+            Some(filesystem) => Ok(filesystem.activate()),
+            None => Err(EngineError::Engine(ErrorEnum::NotFound, uuid.simple().to_string())),
+        }
+    }
+
+    fn deactivate_filesystem(&mut self, uuid: &FilesystemUuid) -> EngineResult<bool> {
+        match self.filesystems.get_mut_by_uuid(uuid) {
+            Some(filesystem) => filesystem.deactivate(),
+            None => Err(EngineError::Engine(ErrorEnum::NotFound, uuid.simple().to_string())),
+        }
+    }
+
+    fn set_mount_point(&mut self, uuid: &FilesystemUuid, mount_point: &Path) -> EngineResult<()> {
+        match self.filesystems.get_mut_by_uuid(uuid) {
+            Some(filesystem) => filesystem.set_mount_point(mount_point),
+            None => Err(EngineError::Engine(ErrorEnum::NotFound, uuid.simple().to_string())),
+        }
+    }
+
     fn rename(&mut self, name: &str) {
         self.name = name.to_owned();
     }
@@ -146,13 +414,35 @@ impl Pool for SimPool {
     }
 
     fn total_physical_size(&self) -> Sectors {
-        // We choose to make our pools very big, and we can change that
-        // if it is inconvenient.
-        Sectors(IEC::Ei)
+        self.blockdev_sizes().iter().cloned().sum()
     }
 
     fn total_physical_used(&self) -> EngineResult<Sectors> {
-        Ok(Sectors(0))
+        Ok(self.block_devs.values().map(|bd| bd.allocated()).sum())
+    }
+
+    fn total_usable_size(&self) -> Sectors {
+        self.redundancy.usable_size(&self.blockdev_sizes())
+    }
+
+    fn largest_free_extent(&self) -> Sectors {
+        self.block_devs
+            .values()
+            .map(|bd| bd.largest_free_extent())
+            .max()
+            .unwrap_or(Sectors(0))
+    }
+
+    fn redundancy_overhead(&self) -> Sectors {
+        self.total_physical_size() - self.total_usable_size()
+    }
+
+    fn logical_capacity(&self) -> Sectors {
+        let usable = self.total_usable_size() * self.overprovision_factor;
+        match self.dedup_ratio {
+            Some(ratio) => Sectors((*usable as f64 * ratio) as u64),
+            None => usable,
+        }
     }
 
     fn filesystems(&self) -> Vec<&Filesystem> {
@@ -168,6 +458,78 @@ impl Pool for SimPool {
             .map(|(_, bd)| bd as &BlockDev)
             .collect()
     }
+
+    fn redundancy(&self) -> &Redundancy {
+        &self.redundancy
+    }
+
+    fn dedup_ratio(&self) -> Option<f64> {
+        self.dedup_ratio
+    }
+
+    fn config_hash(&self) -> String {
+        pool_config_hash(self, &self.redundancy)
+    }
+
+    fn status_detail(&self) -> String {
+        let mut problems = self.block_devs
+            .values()
+            .filter_map(|bd| match bd.status() {
+                            DevStatus::Missing => Some((bd, "missing")),
+                            DevStatus::Failed => Some((bd, "failed")),
+                            DevStatus::Online | DevStatus::Spare => None,
+                        })
+            .map(|(bd, state)| format!("blockdev {} {}", bd.devnode.display(), state))
+            .collect::<Vec<_>>();
+        problems.sort();
+        pool_status_detail(&problems)
+    }
+
+    fn hidden(&self) -> bool {
+        self.hidden
+    }
+
+    fn set_hidden(&mut self, hidden: bool) -> () {
+        self.hidden = hidden;
+    }
+
+    fn filesystem_io_stats(&self, name: &str) -> EngineResult<IoStats> {
+        self.filesystems
+            .get_by_name(name)
+            .map(|fs| fs.io_stats())
+            .ok_or_else(|| {
+                            EngineError::Engine(ErrorEnum::NotFound,
+                                                format!("no filesystem {}", name))
+                        })
+    }
+
+    fn filesystems_by_size(&self, min_size: Sectors, descending: bool) -> Vec<(String, Sectors)> {
+        filesystems_by_size(&self.filesystems(), min_size, descending)
+    }
+
+    fn data_low_watermark(&self) -> u8 {
+        self.data_low_watermark
+    }
+
+    fn metadata_low_watermark(&self) -> u8 {
+        self.metadata_low_watermark
+    }
+
+    fn set_data_low_watermark(&mut self, percent: u8) -> EngineResult<()> {
+        validate_low_watermark(percent)?;
+        self.data_low_watermark = percent;
+        Ok(())
+    }
+
+    fn set_metadata_low_watermark(&mut self, percent: u8) -> EngineResult<()> {
+        validate_low_watermark(percent)?;
+        self.metadata_low_watermark = percent;
+        Ok(())
+    }
+
+    fn device_status_counts(&self) -> HashMap<DevStatus, usize> {
+        pool_device_status_counts(self.block_devs.values().map(|bd| bd.status()))
+    }
 }
 
 impl HasUuid for SimPool {
@@ -186,22 +548,34 @@ impl HasName for SimPool {
 #[cfg(test)]
 mod tests {
 
+    use std::cell::RefCell;
     use std::path::Path;
+    use std::rc::Rc;
 
     use uuid::Uuid;
 
+    use devicemapper::{Bytes, IEC, Sectors};
+
+    use engine::DevStatus;
     use engine::Engine;
     use engine::ErrorEnum;
     use engine::EngineError;
+    use engine::IoStats;
+    use engine::Pool;
+    use engine::Redundancy;
     use engine::RenameAction;
 
     use super::super::SimEngine;
+    use super::super::blockdev::SimDevices;
+    use super::super::randomization::Randomizer;
+
+    use super::{PoolWarning, SimPool};
 
     #[test]
     /// Renaming a filesystem on an empty pool always works
     fn rename_empty() {
         let mut engine = SimEngine::default();
-        let (uuid, _) = engine.create_pool("name", &[], None, false).unwrap();
+        let (uuid, _) = engine.create_pool("name", &[], None, false, false, false).unwrap();
         let pool = engine.get_mut_pool(&uuid).unwrap();
         assert!(match pool.rename_filesystem(&Uuid::new_v4(), "new_name") {
                     Ok(RenameAction::NoSource) => true,
@@ -213,9 +587,11 @@ mod tests {
     /// Renaming a filesystem to another filesystem should work if new name not taken
     fn rename_happens() {
         let mut engine = SimEngine::default();
-        let (uuid, _) = engine.create_pool("name", &[], None, false).unwrap();
+        let (uuid, _) = engine
+            .create_pool("name", &[Path::new("/s/d")], None, false, false, false)
+            .unwrap();
         let pool = engine.get_mut_pool(&uuid).unwrap();
-        let infos = pool.create_filesystems(&[("old_name", None)]).unwrap();
+        let infos = pool.create_filesystems(&[("old_name", None)], false).unwrap();
         assert!(match pool.rename_filesystem(&infos[0].1, "new_name") {
                     Ok(RenameAction::Renamed) => true,
                     _ => false,
@@ -228,9 +604,11 @@ mod tests {
         let old_name = "old_name";
         let new_name = "new_name";
         let mut engine = SimEngine::default();
-        let (uuid, _) = engine.create_pool("name", &[], None, false).unwrap();
+        let (uuid, _) = engine
+            .create_pool("name", &[Path::new("/s/d")], None, false, false, false)
+            .unwrap();
         let pool = engine.get_mut_pool(&uuid).unwrap();
-        let results = pool.create_filesystems(&[(old_name, None), (new_name, None)])
+        let results = pool.create_filesystems(&[(old_name, None), (new_name, None)], false)
             .unwrap();
         let old_uuid = results.iter().find(|x| x.0 == old_name).unwrap().1;
         assert!(match pool.rename_filesystem(&old_uuid, new_name) {
@@ -244,7 +622,7 @@ mod tests {
     fn rename_no_op() {
         let new_name = "new_name";
         let mut engine = SimEngine::default();
-        let (uuid, _) = engine.create_pool("name", &[], None, false).unwrap();
+        let (uuid, _) = engine.create_pool("name", &[], None, false, false, false).unwrap();
         let pool = engine.get_mut_pool(&uuid).unwrap();
         assert!(match pool.rename_filesystem(&Uuid::new_v4(), new_name) {
                     Ok(RenameAction::NoSource) => true,
@@ -256,7 +634,7 @@ mod tests {
     /// Removing an empty list of filesystems should always succeed
     fn destroy_fs_empty() {
         let mut engine = SimEngine::default();
-        let (uuid, _) = engine.create_pool("name", &[], None, false).unwrap();
+        let (uuid, _) = engine.create_pool("name", &[], None, false, false, false).unwrap();
         let pool = engine.get_mut_pool(&uuid).unwrap();
         assert!(match pool.destroy_filesystems(&[]) {
                     Ok(names) => names.is_empty(),
@@ -268,7 +646,7 @@ mod tests {
     /// Removing a non-empty list of filesystems should succeed on empty pool
     fn destroy_fs_some() {
         let mut engine = SimEngine::default();
-        let (uuid, _) = engine.create_pool("name", &[], None, false).unwrap();
+        let (uuid, _) = engine.create_pool("name", &[], None, false, false, false).unwrap();
         let pool = engine.get_mut_pool(&uuid).unwrap();
         assert!(pool.destroy_filesystems(&[&Uuid::new_v4()]).is_ok());
     }
@@ -277,9 +655,11 @@ mod tests {
     /// Removing a non-empty list of filesystems should succeed on any pool
     fn destroy_fs_any() {
         let mut engine = SimEngine::default();
-        let (uuid, _) = engine.create_pool("name", &[], None, false).unwrap();
+        let (uuid, _) = engine
+            .create_pool("name", &[Path::new("/s/d")], None, false, false, false)
+            .unwrap();
         let pool = engine.get_mut_pool(&uuid).unwrap();
-        let fs_results = pool.create_filesystems(&[("fs_name", None)]).unwrap();
+        let fs_results = pool.create_filesystems(&[("fs_name", None)], false).unwrap();
         let fs_uuid = fs_results[0].1;
         assert!(match pool.destroy_filesystems(&[&fs_uuid, &Uuid::new_v4()]) {
                     Ok(filesystems) => filesystems == vec![&fs_uuid],
@@ -292,10 +672,10 @@ mod tests {
     fn create_fs_none() {
         let mut engine = SimEngine::default();
         let (uuid, _) = engine
-            .create_pool("pool_name", &[], None, false)
+            .create_pool("pool_name", &[], None, false, false, false)
             .unwrap();
         let pool = engine.get_mut_pool(&uuid).unwrap();
-        assert!(match pool.create_filesystems(&[]) {
+        assert!(match pool.create_filesystems(&[], false) {
                     Ok(names) => names.is_empty(),
                     _ => false,
                 });
@@ -306,10 +686,10 @@ mod tests {
     fn create_fs_some() {
         let mut engine = SimEngine::default();
         let (uuid, _) = engine
-            .create_pool("pool_name", &[], None, false)
+            .create_pool("pool_name", &[Path::new("/s/d")], None, false, false, false)
             .unwrap();
         let pool = engine.get_mut_pool(&uuid).unwrap();
-        assert!(match pool.create_filesystems(&[("name", None)]) {
+        assert!(match pool.create_filesystems(&[("name", None)], false) {
                     Ok(names) => (names.len() == 1) & (names[0].0 == "name"),
                     _ => false,
                 });
@@ -321,11 +701,11 @@ mod tests {
         let fs_name = "fs_name";
         let mut engine = SimEngine::default();
         let (uuid, _) = engine
-            .create_pool("pool_name", &[], None, false)
+            .create_pool("pool_name", &[Path::new("/s/d")], None, false, false, false)
             .unwrap();
         let pool = engine.get_mut_pool(&uuid).unwrap();
-        pool.create_filesystems(&[(fs_name, None)]).unwrap();
-        assert!(match pool.create_filesystems(&[(fs_name, None)]) {
+        pool.create_filesystems(&[(fs_name, None)], false).unwrap();
+        assert!(match pool.create_filesystems(&[(fs_name, None)], false) {
                     Err(EngineError::Engine(ErrorEnum::AlreadyExists, _)) => true,
                     _ => false,
                 });
@@ -337,21 +717,495 @@ mod tests {
         let fs_name = "fs_name";
         let mut engine = SimEngine::default();
         let (uuid, _) = engine
-            .create_pool("pool_name", &[], None, false)
+            .create_pool("pool_name", &[Path::new("/s/d")], None, false, false, false)
             .unwrap();
         let pool = engine.get_mut_pool(&uuid).unwrap();
-        assert!(match pool.create_filesystems(&[(fs_name, None), (fs_name, None)]) {
+        assert!(match pool.create_filesystems(&[(fs_name, None), (fs_name, None)], false) {
                     Ok(names) => (names.len() == 1) & (names[0].0 == fs_name),
                     _ => false,
                 });
     }
 
+    #[test]
+    /// Swapping the names of two filesystems leaves each UUID with the
+    /// other's former name, and with its own size.
+    fn swap_filesystem_names_both_exist() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("pool_name", &[Path::new("/s/d")], None, false, false, false)
+            .unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        let blue_size = Sectors(IEC::Mi);
+        let green_size = Sectors(2 * IEC::Mi);
+        let results = pool.create_filesystems(&[("blue", Some(blue_size)),
+                                                ("green", Some(green_size))],
+                                              false)
+            .unwrap();
+        let blue_uuid = results.iter().find(|x| x.0 == "blue").unwrap().1;
+        let green_uuid = results.iter().find(|x| x.0 == "green").unwrap().1;
+
+        pool.swap_filesystem_names(&blue_uuid, &green_uuid).unwrap();
+        let blue = pool.get_filesystem(&blue_uuid).unwrap();
+        assert_eq!(blue.name(), "green");
+        assert_eq!(blue.size(), blue_size);
+        let green = pool.get_filesystem(&green_uuid).unwrap();
+        assert_eq!(green.name(), "blue");
+        assert_eq!(green.size(), green_size);
+
+        assert!(match pool.swap_filesystem_names(&blue_uuid, &Uuid::new_v4()) {
+                    Err(EngineError::Engine(ErrorEnum::NotFound, _)) => true,
+                    _ => false,
+                });
+    }
+
+    #[test]
+    /// Mounting an inactive filesystem fails, but succeeds once the
+    /// filesystem has been activated.
+    fn set_mount_point_inactive() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("pool_name", &[Path::new("/s/d")], None, false, false, false)
+            .unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        let fs_uuid = pool.create_filesystems(&[("fs_name", None)], false).unwrap()[0].1;
+        let mount_point = Path::new("/mnt/fs_name");
+        assert!(pool.deactivate_filesystem(&fs_uuid).unwrap());
+        assert!(match pool.set_mount_point(&fs_uuid, mount_point) {
+                    Err(EngineError::Engine(ErrorEnum::Invalid, _)) => true,
+                    _ => false,
+                });
+        assert!(pool.activate_filesystem(&fs_uuid).unwrap());
+        assert!(pool.set_mount_point(&fs_uuid, mount_point).is_ok());
+    }
+
+    #[test]
+    /// Requiring redundancy prevents creating a filesystem on a pool with no
+    /// redundancy, but not on a RAID1 pool.
+    fn create_fs_require_redundancy() {
+        let mut engine = SimEngine::default();
+        let (none_uuid, _) = engine
+            .create_pool("none_pool", &[Path::new("/s/d")], None, false, false, false)
+            .unwrap();
+        let (raid1_uuid, _) = engine
+            .create_pool("raid1_pool",
+                         &[Path::new("/s/e"), Path::new("/s/f")],
+                         Some(u16::from(Redundancy::RAID1)),
+                         false,
+                         false,
+                         false)
+            .unwrap();
+        assert!(match engine
+                          .get_mut_pool(&none_uuid)
+                          .unwrap()
+                          .create_filesystems(&[("fs_name", None)], true) {
+                    Err(EngineError::Engine(ErrorEnum::NotRedundant, _)) => true,
+                    _ => false,
+                });
+        assert!(engine
+                    .get_mut_pool(&raid1_uuid)
+                    .unwrap()
+                    .create_filesystems(&[("fs_name", None)], true)
+                    .is_ok());
+    }
+
+    #[test]
+    /// Requesting a filesystem whose size overflows fails cleanly.
+    fn create_fs_too_large() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("pool_name", &[Path::new("/s/d")], None, false, false, false)
+            .unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        let specs = [("fs_name", Some(Sectors(u64::max_value())))];
+        assert!(match pool.create_filesystems(&specs, false) {
+                    Err(EngineError::Engine(ErrorEnum::Invalid, _)) => true,
+                    _ => false,
+                });
+        assert!(pool.filesystems().is_empty());
+    }
+
+    #[test]
+    /// A pool created with dedup reports a ratio, one created without does not.
+    fn dedup_ratio_some_none() {
+        let mut engine = SimEngine::default();
+        let (dedup_uuid, _) = engine
+            .create_pool("dedup_pool", &[Path::new("/s/d")], None, false, true, false)
+            .unwrap();
+        let (plain_uuid, _) = engine
+            .create_pool("plain_pool", &[Path::new("/s/e")], None, false, false, false)
+            .unwrap();
+        assert!(engine
+                    .get_pool(&dedup_uuid)
+                    .unwrap()
+                    .dedup_ratio()
+                    .is_some());
+        assert!(engine
+                    .get_pool(&plain_uuid)
+                    .unwrap()
+                    .dedup_ratio()
+                    .is_none());
+    }
+
+    #[test]
+    /// A pool over-provisioned by a factor of 2 has a logical capacity twice
+    /// its physical size.
+    fn logical_capacity_overprovisioned() {
+        let mut pool = SimPool::new(Rc::new(RefCell::new(Randomizer::default())),
+                                    Rc::new(RefCell::new(SimDevices::default())),
+                                    "name",
+                                    &[Path::new("/s/a")],
+                                    Redundancy::NONE,
+                                    false);
+        pool.set_overprovision_factor(2);
+        assert_eq!(pool.logical_capacity(), pool.total_physical_size() * 2u64);
+    }
+
+    #[test]
+    /// A 4 device RAID1 pool can use half its raw capacity.
+    fn total_usable_size_raid1() {
+        let mut engine = SimEngine::default();
+        let devices = [Path::new("/s/a"), Path::new("/s/b"), Path::new("/s/c"), Path::new("/s/d")];
+        let (uuid, _) = engine
+            .create_pool("pool_name",
+                         &devices,
+                         Some(u16::from(Redundancy::RAID1)),
+                         false,
+                         false,
+                         false)
+            .unwrap();
+        let pool = engine.get_pool(&uuid).unwrap();
+        assert_eq!(pool.total_usable_size() * 2u64, pool.total_physical_size());
+    }
+
+    #[test]
+    /// A RAID1 pool spends half its raw capacity on redundancy.
+    fn redundancy_overhead_raid1() {
+        let mut engine = SimEngine::default();
+        let devices = [Path::new("/s/a"), Path::new("/s/b")];
+        let (uuid, _) = engine
+            .create_pool("pool_name",
+                         &devices,
+                         Some(u16::from(Redundancy::RAID1)),
+                         false,
+                         false,
+                         false)
+            .unwrap();
+        let pool = engine.get_pool(&uuid).unwrap();
+        assert_eq!(pool.redundancy_overhead() * 2u64, pool.total_physical_size());
+    }
+
+    #[test]
+    /// A watermark may be set to any percentage but 0, and to nothing more.
+    fn set_watermarks() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine.create_pool("name", &[], None, false, false, false).unwrap();
+        let pool = engine.get_mut_pool(&uuid).unwrap();
+        pool.set_metadata_low_watermark(50).unwrap();
+        assert_eq!(pool.metadata_low_watermark(), 50);
+        assert!(match pool.set_data_low_watermark(101) {
+                    Err(EngineError::Engine(ErrorEnum::Invalid, _)) => true,
+                    _ => false,
+                });
+        assert!(match pool.set_metadata_low_watermark(0) {
+                    Err(EngineError::Engine(ErrorEnum::Invalid, _)) => true,
+                    _ => false,
+                });
+        assert!(pool.data_low_watermark() <= 100);
+        assert_eq!(pool.metadata_low_watermark(), 50);
+    }
+
+    #[test]
+    /// Once metadata usage rises past a low metadata watermark, the check
+    /// warns exactly once; it warns again only after usage has fallen below
+    /// the watermark and risen past it again.
+    fn metadata_watermark_warns_once() {
+        let mut pool = SimPool::new(Rc::new(RefCell::new(Randomizer::default())),
+                                    Rc::new(RefCell::new(SimDevices::default())),
+                                    "name",
+                                    &[Path::new("/s/a")],
+                                    Redundancy::NONE,
+                                    false);
+        pool.set_metadata_low_watermark(10).unwrap();
+        pool.create_filesystems(&[("fs1", None)], false).unwrap();
+        assert_eq!(pool.check().unwrap(), vec![]);
+
+        let fs_uuid = pool.create_filesystems(&[("fs2", None)], false).unwrap()[0].1;
+        assert_eq!(pool.check().unwrap(), vec![PoolWarning::MetadataLowSpace]);
+        assert_eq!(pool.check().unwrap(), vec![]);
+
+        pool.destroy_filesystems(&[&fs_uuid]).unwrap();
+        assert_eq!(pool.check().unwrap(), vec![]);
+        pool.create_filesystems(&[("fs2", None)], false).unwrap();
+        assert_eq!(pool.check().unwrap(), vec![PoolWarning::MetadataLowSpace]);
+    }
+
+    #[test]
+    /// The config hash of a pool does not depend on its name or on the order
+    /// in which its devices were specified.
+    fn config_hash_device_order() {
+        let rdm = Rc::new(RefCell::new(Randomizer::default()));
+        let sim_devices = Rc::new(RefCell::new(SimDevices::default()));
+        let pool_a = SimPool::new(Rc::clone(&rdm),
+                                  Rc::clone(&sim_devices),
+                                  "pool_a",
+                                  &[Path::new("/s/a"), Path::new("/s/b"), Path::new("/s/c")],
+                                  Redundancy::NONE,
+                                  false);
+        let pool_b = SimPool::new(Rc::clone(&rdm),
+                                  Rc::clone(&sim_devices),
+                                  "pool_b",
+                                  &[Path::new("/s/c"), Path::new("/s/a"), Path::new("/s/b")],
+                                  Redundancy::NONE,
+                                  false);
+        let pool_c = SimPool::new(rdm,
+                                  sim_devices,
+                                  "pool_c",
+                                  &[Path::new("/s/a")],
+                                  Redundancy::NONE,
+                                  false);
+        assert_eq!(pool_a.config_hash(), pool_b.config_hash());
+        assert_ne!(pool_a.config_hash(), pool_c.config_hash());
+    }
+
+    #[test]
+    /// The config hash of a pool is the SHA-256 digest of its configuration.
+    fn config_hash_sha256() {
+        let pool = SimPool::new(Rc::new(RefCell::new(Randomizer::default())),
+                                Rc::new(RefCell::new(SimDevices::default())),
+                                "name",
+                                &[Path::new("/s/a")],
+                                Redundancy::NONE,
+                                false);
+        // The digest of "redundancy=NONE\nblockdev=/s/a\n".
+        assert_eq!(pool.config_hash(),
+                   "c54615ba12a91556dcbd423f145ec37ac19ede19cb88a433150731c4b305a941");
+    }
+
+    #[test]
+    /// The config hash of a pool depends on the sizes of its filesystems.
+    fn config_hash_filesystem_size() {
+        let rdm = Rc::new(RefCell::new(Randomizer::default()));
+        let sim_devices = Rc::new(RefCell::new(SimDevices::default()));
+        let mut pool_a = SimPool::new(Rc::clone(&rdm),
+                                      Rc::clone(&sim_devices),
+                                      "pool_a",
+                                      &[Path::new("/s/a")],
+                                      Redundancy::NONE,
+                                      false);
+        let mut pool_b = SimPool::new(rdm,
+                                      sim_devices,
+                                      "pool_b",
+                                      &[Path::new("/s/a")],
+                                      Redundancy::NONE,
+                                      false);
+        pool_a
+            .create_filesystems(&[("fs", Some(Sectors(IEC::Mi)))], false)
+            .unwrap();
+        pool_b
+            .create_filesystems(&[("fs", Some(Sectors(2 * IEC::Mi)))], false)
+            .unwrap();
+        assert_ne!(pool_a.config_hash(), pool_b.config_hash());
+    }
+
+    #[test]
+    /// Evacuating a blockdev succeeds if the other blockdevs have room for
+    /// its data, and fails if they do not.
+    fn evacuate_blockdev_space() {
+        let devices = [Path::new("/s/a"), Path::new("/s/b")];
+        let mut pool = SimPool::new(Rc::new(RefCell::new(Randomizer::default())),
+                                    Rc::new(RefCell::new(SimDevices::default())),
+                                    "name",
+                                    &devices,
+                                    Redundancy::NONE,
+                                    false);
+
+        let half = Sectors(*pool.block_devs[devices[0]].avail_space() / 2);
+        pool.block_devs
+            .get_mut(devices[0])
+            .unwrap()
+            .alloc_space(half);
+        assert!(pool.evacuate_blockdev(devices[0]).is_ok());
+        assert_eq!(pool.block_devs[devices[0]].allocated(), Sectors(0));
+        assert_eq!(pool.block_devs[devices[1]].allocated(), half);
+        assert_eq!(pool.total_physical_size(), pool.block_devs[devices[1]].size());
+
+        let full = pool.block_devs[devices[1]].avail_space();
+        pool.block_devs
+            .get_mut(devices[1])
+            .unwrap()
+            .alloc_space(full);
+        assert!(match pool.evacuate_blockdev(devices[1]) {
+                    Err(EngineError::Engine(ErrorEnum::InsufficientSpace, _)) => true,
+                    _ => false,
+                });
+    }
+
+    #[test]
+    /// The status detail of a pool names a blockdev which has gone missing.
+    fn status_detail_missing_device() {
+        let devices = [Path::new("/s/a"), Path::new("/s/b")];
+        let mut pool = SimPool::new(Rc::new(RefCell::new(Randomizer::default())),
+                                    Rc::new(RefCell::new(SimDevices::default())),
+                                    "name",
+                                    &devices,
+                                    Redundancy::NONE,
+                                    false);
+        assert!(!pool.status_detail().contains("/s/b"));
+        pool.block_devs
+            .get_mut(devices[1])
+            .unwrap()
+            .set_present(false);
+        assert!(pool.status_detail().contains("/s/b"));
+        assert!(!pool.status_detail().contains("/s/a"));
+    }
+
+    #[test]
+    /// The IO stats of a filesystem are those it was set to report; a
+    /// filesystem which does not exist has none.
+    fn filesystem_io_stats() {
+        let mut pool = SimPool::new(Rc::new(RefCell::new(Randomizer::default())),
+                                    Rc::new(RefCell::new(SimDevices::default())),
+                                    "name",
+                                    &[Path::new("/s/a")],
+                                    Redundancy::NONE,
+                                    false);
+        pool.create_filesystems(&[("fs", None)], false).unwrap();
+        let io_stats = IoStats {
+            read_bytes: Bytes(4096),
+            write_bytes: Bytes(512),
+            read_ops: 2,
+            write_ops: 1,
+        };
+        pool.filesystems
+            .get_mut_by_name("fs")
+            .unwrap()
+            .set_io_stats(io_stats);
+        assert_eq!(pool.filesystem_io_stats("fs").unwrap(), io_stats);
+        assert!(match pool.filesystem_io_stats("other") {
+                    Err(EngineError::Engine(ErrorEnum::NotFound, _)) => true,
+                    _ => false,
+                });
+    }
+
+    #[test]
+    /// Filesystems are listed largest first when descending, omitting any
+    /// smaller than the minimum.
+    fn filesystems_by_size_descending() {
+        let mut pool = SimPool::new(Rc::new(RefCell::new(Randomizer::default())),
+                                    Rc::new(RefCell::new(SimDevices::default())),
+                                    "name",
+                                    &[Path::new("/s/a")],
+                                    Redundancy::NONE,
+                                    false);
+        pool.create_filesystems(&[("small", Some(Sectors(1024))),
+                                  ("medium", Some(Sectors(2048))),
+                                  ("large", Some(Sectors(4096)))],
+                                false)
+            .unwrap();
+        assert_eq!(pool.filesystems_by_size(Sectors(2048), true),
+                   vec![("large".to_owned(), Sectors(4096)),
+                        ("medium".to_owned(), Sectors(2048))]);
+        assert_eq!(pool.filesystems_by_size(Sectors(0), false)[0].0, "small");
+    }
+
+    #[test]
+    /// Destroying every other one of a run of filesystems leaves the pool's
+    /// free space in pieces, none as large as all of it.
+    fn largest_free_extent_fragmented() {
+        let mut pool = SimPool::new(Rc::new(RefCell::new(Randomizer::default())),
+                                    Rc::new(RefCell::new(SimDevices::default())),
+                                    "name",
+                                    &[Path::new("/s/a")],
+                                    Redundancy::NONE,
+                                    false);
+        let free = pool.block_devs[Path::new("/s/a")].avail_space();
+        assert_eq!(pool.largest_free_extent(), free);
+
+        let uuids = ["fs0", "fs1", "fs2", "fs3"]
+            .iter()
+            .map(|name| pool.create_filesystems(&[(name, None)], false).unwrap()[0].1)
+            .collect::<Vec<_>>();
+        pool.destroy_filesystems(&[&uuids[0], &uuids[2]]).unwrap();
+
+        let free = free - pool.total_physical_used().unwrap();
+        assert!(pool.largest_free_extent() < free);
+        assert_eq!(pool.block_devs[Path::new("/s/a")].avail_space(), free);
+    }
+
+    #[test]
+    /// Devices which have gone missing are counted separately from those
+    /// which are online.
+    fn device_status_counts_missing_device() {
+        let devices = [Path::new("/s/a"), Path::new("/s/b"), Path::new("/s/c")];
+        let mut pool = SimPool::new(Rc::new(RefCell::new(Randomizer::default())),
+                                    Rc::new(RefCell::new(SimDevices::default())),
+                                    "name",
+                                    &devices,
+                                    Redundancy::NONE,
+                                    false);
+        pool.block_devs
+            .get_mut(devices[2])
+            .unwrap()
+            .set_present(false);
+        let counts = pool.device_status_counts();
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[&DevStatus::Online], 2);
+        assert_eq!(counts[&DevStatus::Missing], 1);
+    }
+
+    #[test]
+    /// Devices which have failed are counted separately from those which
+    /// are online.
+    fn device_status_counts_failed_device() {
+        let devices = [Path::new("/s/a"), Path::new("/s/b"), Path::new("/s/c")];
+        let mut pool = SimPool::new(Rc::new(RefCell::new(Randomizer::default())),
+                                    Rc::new(RefCell::new(SimDevices::default())),
+                                    "name",
+                                    &devices,
+                                    Redundancy::NONE,
+                                    false);
+        pool.block_devs
+            .get_mut(devices[2])
+            .unwrap()
+            .set_status(DevStatus::Failed);
+        let counts = pool.device_status_counts();
+        assert_eq!(counts.len(), 2);
+        assert_eq!(counts[&DevStatus::Online], 2);
+        assert_eq!(counts[&DevStatus::Failed], 1);
+    }
+
+    #[test]
+    /// Adding a device which belongs to another pool should fail, until that
+    /// pool is destroyed.
+    fn add_device_busy() {
+        let mut engine = SimEngine::default();
+        let (uuid, _) = engine
+            .create_pool("pool_name", &[Path::new("/s/d")], None, false, false, false)
+            .unwrap();
+        let (other_uuid, _) = engine
+            .create_pool("other_name", &[Path::new("/s/e")], None, false, false, false)
+            .unwrap();
+        assert!(match engine
+                          .get_mut_pool(&uuid)
+                          .unwrap()
+                          .add_blockdevs(&[Path::new("/s/e")], false) {
+                    Err(EngineError::Engine(ErrorEnum::Busy, _)) => true,
+                    _ => false,
+                });
+        engine.destroy_pool(&other_uuid).unwrap();
+        assert!(engine
+                    .get_mut_pool(&uuid)
+                    .unwrap()
+                    .add_blockdevs(&[Path::new("/s/e")], false)
+                    .is_ok());
+    }
+
     #[test]
     /// Adding a list of devices to an empty pool should yield list.
     fn add_device_empty() {
         let mut engine = SimEngine::default();
         let (uuid, _) = engine
-            .create_pool("pool_name", &[], None, false)
+            .create_pool("pool_name", &[], None, false, false, false)
             .unwrap();
         let pool = engine.get_mut_pool(&uuid).unwrap();
         let devices = [Path::new("/s/a"), Path::new("/s/b")];
@@ -112,6 +112,12 @@ impl StratBlockDev {
         self.used.available()
     }
 
+    /// The number of Sectors in the largest contiguous range on this
+    /// device not allocated for any purpose.
+    pub fn largest_available(&self) -> Sectors {
+        self.used.largest_available()
+    }
+
     /// The maximum size of variable length metadata that can be accommodated.
     /// self.max_metadata_size() < self.metadata_size()
     pub fn max_metadata_size(&self) -> Sectors {
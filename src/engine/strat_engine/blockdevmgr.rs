@@ -5,7 +5,7 @@
 // Code to handle a collection of block devices.
 
 use std::collections::HashMap;
-use std::fs::{File, OpenOptions};
+use std::fs::File;
 use std::path::{Path, PathBuf};
 
 use chrono::{DateTime, Duration, Utc};
@@ -16,17 +16,17 @@ use devicemapper::{Bytes, Device, IEC, Sectors, Segment};
 
 use super::super::engine::BlockDev;
 use super::super::errors::{EngineError, EngineResult, ErrorEnum};
-use super::super::types::{DevUuid, PoolUuid};
+use super::super::types::{DevUuid, OpenMode, PoolUuid};
 
 use super::cleanup::wipe_blockdevs;
 use super::blockdev::StratBlockDev;
-use super::device::{blkdev_size, resolve_devices};
+use super::device::{blkdev_size, open_blockdev, resolve_devices};
 use super::engine::DevOwnership;
 use super::metadata::{BDA, MIN_MDA_SECTORS, StaticHeader, validate_mda_size};
 use super::range_alloc::RangeAllocator;
 use super::serde_structs::{BlockDevSave, Recordable};
 
-const MIN_DEV_SIZE: Bytes = Bytes(IEC::Gi);
+pub const MIN_DEV_SIZE: Bytes = Bytes(IEC::Gi);
 const MAX_NUM_TO_WRITE: usize = 10;
 
 #[derive(Debug)]
@@ -81,10 +81,11 @@ impl BlockDevMgr {
     pub fn initialize(pool_uuid: &PoolUuid,
                       paths: &[&Path],
                       mda_size: Sectors,
-                      force: bool)
+                      force: bool,
+                      open_mode: OpenMode)
                       -> EngineResult<BlockDevMgr> {
         let devices = resolve_devices(paths)?;
-        Ok(BlockDevMgr::new(initialize(pool_uuid, devices, mda_size, force)?))
+        Ok(BlockDevMgr::new(initialize(pool_uuid, devices, mda_size, force, open_mode)?))
     }
 
     /// Get a function that maps UUIDs to Devices.
@@ -100,10 +101,11 @@ impl BlockDevMgr {
     pub fn add(&mut self,
                pool_uuid: &PoolUuid,
                paths: &[&Path],
-               force: bool)
+               force: bool,
+               open_mode: OpenMode)
                -> EngineResult<Vec<PathBuf>> {
         let devices = resolve_devices(paths)?;
-        let bds = initialize(pool_uuid, devices, MIN_MDA_SECTORS, force)?;
+        let bds = initialize(pool_uuid, devices, MIN_MDA_SECTORS, force, open_mode)?;
         let bdev_paths = bds.iter().map(|p| p.devnode.clone()).collect();
         self.block_devs.extend(bds);
         Ok(bdev_paths)
@@ -218,6 +220,16 @@ impl BlockDevMgr {
         self.block_devs.iter().map(|bd| bd.available()).sum()
     }
 
+    /// The number of sectors in the largest contiguous range on any one
+    /// blockdev which is not allocated for any purpose.
+    pub fn largest_avail_extent(&self) -> Sectors {
+        self.block_devs
+            .iter()
+            .map(|bd| bd.largest_available())
+            .max()
+            .unwrap_or(Sectors(0))
+    }
+
     /// The current capacity of all the blockdevs.
     /// self.current_capacity() > self.avail_space() because some sectors
     /// are certainly allocated for Stratis metadata
@@ -249,10 +261,12 @@ impl Recordable<HashMap<DevUuid, BlockDevSave>> for BlockDevMgr {
 
 /// Initialize multiple blockdevs at once. This allows all of them
 /// to be checked for usability before writing to any of them.
+/// Each device is opened according to open_mode.
 pub fn initialize(pool_uuid: &PoolUuid,
                   devices: HashMap<Device, &Path>,
                   mda_size: Sectors,
-                  force: bool)
+                  force: bool,
+                  open_mode: OpenMode)
                   -> EngineResult<Vec<StratBlockDev>> {
 
     /// Get device information, returns an error if problem with obtaining
@@ -260,11 +274,10 @@ pub fn initialize(pool_uuid: &PoolUuid,
     /// Returns a tuple with the device's path, its size in bytes,
     /// its ownership as determined by calling determine_ownership(),
     /// and an open File handle, all of which are needed later.
-    pub fn dev_info(devnode: &Path) -> EngineResult<(&Path, Bytes, DevOwnership, File)> {
-        let mut f = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .open(&devnode)?;
+    pub fn dev_info(devnode: &Path,
+                    open_mode: OpenMode)
+                    -> EngineResult<(&Path, Bytes, DevOwnership, File)> {
+        let mut f = open_blockdev(devnode, open_mode)?;
         let dev_size = blkdev_size(&f)?;
         let ownership = StaticHeader::determine_ownership(&mut f)?;
 
@@ -320,7 +333,9 @@ pub fn initialize(pool_uuid: &PoolUuid,
 
     validate_mda_size(mda_size)?;
 
-    let dev_infos = devices.into_iter().map(|(d, p)| (d, dev_info(p)));
+    let dev_infos = devices
+        .into_iter()
+        .map(|(d, p)| (d, dev_info(p, open_mode)));
 
     let add_devs = filter_devs(dev_infos, pool_uuid, force)?;
 
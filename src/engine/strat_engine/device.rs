@@ -6,17 +6,21 @@
 
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::{BufWriter, ErrorKind, Seek, SeekFrom, Write};
+use std::io::{BufWriter, ErrorKind, Read, Seek, SeekFrom, Write};
 use std::fs::OpenOptions;
 use std::os::linux::fs::MetadataExt;
+use std::os::unix::fs::OpenOptionsExt;
 use std::os::unix::prelude::AsRawFd;
 use std::path::Path;
 
+use nix::Errno;
+use nix::libc::O_EXCL;
 use nix::sys::stat::{S_IFBLK, S_IFMT};
 
 use devicemapper::{Bytes, Device, IEC, SECTOR_SIZE, Sectors};
 
 use super::super::errors::{EngineResult, EngineError, ErrorEnum};
+use super::super::types::{IoStats, OpenMode};
 
 ioctl!(read blkgetsize64 with 0x12, 114; u64);
 
@@ -29,6 +33,29 @@ pub fn blkdev_size(file: &File) -> EngineResult<Bytes> {
     }
 }
 
+/// Open a block device for reading and writing.
+/// If mode is OpenMode::Exclusive, the device is opened with O_EXCL, and
+/// a Busy error is returned if some other process holds the device.
+pub fn open_blockdev(devnode: &Path, mode: OpenMode) -> EngineResult<File> {
+    let mut options = OpenOptions::new();
+    options.read(true).write(true);
+    if mode == OpenMode::Exclusive {
+        options.custom_flags(O_EXCL);
+    }
+
+    match options.open(devnode) {
+        Ok(f) => Ok(f),
+        Err(err) => {
+            if err.raw_os_error().map(Errno::from_i32) == Some(Errno::EBUSY) {
+                let err_msg = format!("device {} is in use by another process",
+                                      devnode.display());
+                return Err(EngineError::Engine(ErrorEnum::Busy, err_msg));
+            }
+            Err(EngineError::Io(err))
+        }
+    }
+}
+
 /// Write buf at offset length times.
 pub fn write_sectors<P: AsRef<Path>>(path: P,
                                      offset: Sectors,
@@ -93,3 +120,80 @@ pub fn resolve_devices<'a>(paths: &'a [&Path]) -> EngineResult<HashMap<Device, &
     }
     Ok(map)
 }
+
+/// The IO statistics which the kernel keeps for a block device.
+pub fn io_stats(device: Device) -> EngineResult<IoStats> {
+    let mut stat = String::new();
+    File::open(format!("/sys/dev/block/{}/stat", device))?
+        .read_to_string(&mut stat)?;
+    parse_io_stats(&stat)
+}
+
+/// Whether the block device is mounted anywhere.
+pub fn is_mounted(device: Device) -> EngineResult<bool> {
+    let mut mountinfo = String::new();
+    File::open("/proc/self/mountinfo")?
+        .read_to_string(&mut mountinfo)?;
+    Ok(mountinfo_has_device(&mountinfo, device))
+}
+
+/// Whether the contents of a mountinfo file list a mount of device. The
+/// third field of each line is the device of that mount; the format is
+/// described in the kernel's Documentation/filesystems/proc.txt.
+fn mountinfo_has_device(mountinfo: &str, device: Device) -> bool {
+    let device = device.to_string();
+    mountinfo
+        .lines()
+        .any(|line| line.split_whitespace().nth(2) == Some(&device))
+}
+
+/// Parse the contents of a block device's sysfs stat file. Its fields are
+/// described in the kernel's Documentation/block/stat.txt; the reads and
+/// writes completed are the first and fifth, the sectors read and written
+/// the third and seventh.
+fn parse_io_stats(stat: &str) -> EngineResult<IoStats> {
+    let fields = stat.split_whitespace()
+        .take(7)
+        .map(|f| f.parse::<u64>())
+        .collect::<Result<Vec<u64>, _>>()
+        .map_err(|err| EngineError::Engine(ErrorEnum::Invalid, err.to_string()))?;
+    if fields.len() < 7 {
+        let err_msg = format!("block device stats \"{}\" have too few fields", stat.trim());
+        return Err(EngineError::Engine(ErrorEnum::Invalid, err_msg));
+    }
+    Ok(IoStats {
+           read_bytes: Sectors(fields[2]).bytes(),
+           write_bytes: Sectors(fields[6]).bytes(),
+           read_ops: fields[0],
+           write_ops: fields[4],
+       })
+}
+
+#[cfg(test)]
+mod tests {
+    use devicemapper::{Device, Sectors};
+
+    use super::{mountinfo_has_device, parse_io_stats};
+
+    #[test]
+    /// Counts of operations and of sectors read and written are taken from
+    /// their fields; a truncated line is an error.
+    fn io_stats_fields() {
+        let stats = parse_io_stats("  120  3  4096  50  7  0  512  9  0  60  59\n").unwrap();
+        assert_eq!(stats.read_ops, 120);
+        assert_eq!(stats.read_bytes, Sectors(4096).bytes());
+        assert_eq!(stats.write_ops, 7);
+        assert_eq!(stats.write_bytes, Sectors(512).bytes());
+        assert!(parse_io_stats("120 3 4096").is_err());
+    }
+
+    #[test]
+    /// A device is mounted only if some line of mountinfo names it as the
+    /// device of its mount.
+    fn mountinfo_device() {
+        let mountinfo = "22 1 253:0 / / rw,relatime shared:1 - xfs /dev/mapper/root rw\n\
+                         40 22 253:3 / /mnt rw,relatime shared:2 - xfs /dev/dm-3 rw\n";
+        assert!(mountinfo_has_device(mountinfo, Device { major: 253, minor: 3 }));
+        assert!(!mountinfo_has_device(mountinfo, Device { major: 253, minor: 30 }));
+    }
+}
@@ -2,21 +2,34 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::cmp::max;
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
 use std::path::Path;
 use std::path::PathBuf;
 
 use uuid::Uuid;
 
-use devicemapper::DM;
+use devicemapper::{DM, Sectors};
 
 use super::super::engine::{Engine, HasName, HasUuid, Pool};
 use super::super::errors::{EngineError, EngineResult, ErrorEnum};
 use super::super::structures::Table;
-use super::super::types::{PoolUuid, Redundancy, RenameAction};
+use super::super::types::{DestroyedPoolInfo, EngineKind, OpenMode, PoolSpec, PoolUuid,
+                          Redundancy, RenameAction};
+use super::super::util::{SUPPORTED_FS_TYPES, apply_pool_spec, filesystems_affected_by_device,
+                         min_device_size, pools_metrics_prometheus, record_destroyed_pool,
+                         validate_device_count, validate_uniform_sizes};
 
+use super::blockdevmgr::MIN_DEV_SIZE;
 use super::cleanup::teardown_pools;
+use super::device::{blkdev_size, open_blockdev, resolve_devices};
+use super::mdv::DEV_PATH;
+use super::metadata::{BDA, MIN_MDA_SECTORS, StaticHeader};
 use super::pool::StratPool;
 use super::setup::find_all;
+use super::thinpool::ThinPool;
+use super::util::probe_signature;
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum DevOwnership {
@@ -28,6 +41,7 @@ pub enum DevOwnership {
 #[derive(Debug)]
 pub struct StratEngine {
     pools: Table<StratPool>,
+    destroyed: VecDeque<DestroyedPoolInfo>,
 }
 
 impl StratEngine {
@@ -53,7 +67,10 @@ impl StratEngine {
             }
         }
 
-        Ok(StratEngine { pools: table })
+        Ok(StratEngine {
+               pools: table,
+               destroyed: VecDeque::new(),
+           })
     }
 
     /// Teardown Stratis, preparatory to a shutdown.
@@ -76,17 +93,48 @@ impl Engine for StratEngine {
                    name: &str,
                    blockdev_paths: &[&Path],
                    redundancy: Option<u16>,
-                   force: bool)
+                   force: bool,
+                   dedup: bool,
+                   require_uniform_devices: bool)
                    -> EngineResult<(PoolUuid, Vec<PathBuf>)> {
 
         let redundancy = calculate_redundancy!(redundancy);
 
+        if redundancy != Redundancy::NONE {
+            let err_msg = format!("redundancy {} is not supported", redundancy);
+            return Err(EngineError::Engine(ErrorEnum::Invalid, err_msg));
+        }
+
+        if dedup {
+            let err_msg = "deduplication is not supported";
+            return Err(EngineError::Engine(ErrorEnum::Invalid, err_msg.into()));
+        }
+
         if self.pools.contains_name(name) {
             return Err(EngineError::Engine(ErrorEnum::AlreadyExists, name.into()));
         }
 
+        let devices = resolve_devices(blockdev_paths)?;
+        validate_device_count(&redundancy, devices.len())?;
+
+        if require_uniform_devices {
+            let sizes = devices
+                .values()
+                .map(|path| {
+                         let f = open_blockdev(path, OpenMode::Shared)?;
+                         Ok(blkdev_size(&f)?.sectors())
+                     })
+                .collect::<EngineResult<Vec<_>>>()?;
+            validate_uniform_sizes(&sizes)?;
+        }
+
         let dm = DM::new()?;
-        let (pool, devnodes) = StratPool::initialize(name, &dm, blockdev_paths, redundancy, force)?;
+        let (pool, devnodes) = StratPool::initialize(name,
+                                                     &dm,
+                                                     blockdev_paths,
+                                                     redundancy,
+                                                     force,
+                                                     OpenMode::Exclusive)?;
 
         let uuid = *pool.uuid();
         self.pools.insert(pool);
@@ -130,4 +178,64 @@ impl Engine for StratEngine {
     fn pools(&self) -> Vec<&Pool> {
         self.pools.into_iter().map(|x| x as &Pool).collect()
     }
+
+    fn list_pools(&self, include_hidden: bool) -> Vec<&Pool> {
+        self.pools
+            .into_iter()
+            .map(|x| x as &Pool)
+            .filter(|x| include_hidden || !x.hidden())
+            .collect()
+    }
+
+    fn supported_fs_types(&self) -> Vec<&str> {
+        SUPPORTED_FS_TYPES.to_vec()
+    }
+
+    fn kind(&self) -> EngineKind {
+        EngineKind::Real
+    }
+
+    fn data_dir(&self) -> &Path {
+        Path::new(DEV_PATH)
+    }
+
+    fn recently_destroyed(&self) -> Vec<DestroyedPoolInfo> {
+        self.destroyed.iter().cloned().collect()
+    }
+
+    fn filesystems_affected_by_device(&self, path: &Path) -> EngineResult<Vec<String>> {
+        filesystems_affected_by_device(&self.pools(), path)
+    }
+
+    fn device_signature(&self, path: &Path) -> EngineResult<Option<String>> {
+        let mut f = OpenOptions::new().read(true).open(path)?;
+        match StaticHeader::determine_ownership(&mut f)? {
+            DevOwnership::Ours(_) => Ok(Some("stratis".into())),
+            DevOwnership::Unowned => Ok(None),
+            DevOwnership::Theirs => {
+                Ok(Some(probe_signature(path)?.unwrap_or_else(|| "unknown".into())))
+            }
+        }
+    }
+
+    fn min_device_size_for(&self,
+                           volume_size: Sectors,
+                           redundancy: &Redundancy,
+                           device_count: usize)
+                           -> EngineResult<Sectors> {
+        let size = min_device_size(volume_size,
+                                   redundancy,
+                                   device_count,
+                                   ThinPool::initial_size(),
+                                   BDA::size_for(MIN_MDA_SECTORS))?;
+        Ok(max(size, MIN_DEV_SIZE.sectors()))
+    }
+
+    fn apply_pool_spec(&mut self, spec: &PoolSpec) -> EngineResult<Vec<String>> {
+        apply_pool_spec(self, spec)
+    }
+
+    fn metrics_prometheus(&self) -> String {
+        pools_metrics_prometheus(&self.pools())
+    }
 }
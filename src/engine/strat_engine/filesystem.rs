@@ -5,8 +5,8 @@
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
-use devicemapper::{Bytes, DmDevice, DM, DmName, IEC, SECTOR_SIZE, Sectors, ThinDev, ThinDevId,
-                   ThinStatus, ThinPoolDev};
+use devicemapper::{Bytes, DevId, DmDevice, DmFlags, DM, DmName, IEC, SECTOR_SIZE, Sectors,
+                   ThinDev, ThinDevId, ThinStatus, ThinPoolDev};
 
 use nix::sys::statvfs::statvfs;
 use nix::sys::statvfs::vfs::Statvfs;
@@ -15,8 +15,11 @@ use tempdir::TempDir;
 
 use super::super::engine::{Filesystem, HasName, HasUuid};
 use super::super::errors::{EngineError, EngineResult, ErrorEnum};
-use super::super::types::FilesystemUuid;
+use super::super::types::{FilesystemUuid, IoStats};
 
+use super::super::util::SUPPORTED_FS_TYPES;
+
+use super::device::{io_stats, is_mounted};
 use super::serde_structs::{FilesystemSave, Recordable};
 use super::util::{create_fs, set_uuid, xfs_growfs};
 
@@ -24,11 +27,15 @@ use super::util::{create_fs, set_uuid, xfs_growfs};
 /// the filesystem is out of space.
 pub const FILESYSTEM_LOWATER: Sectors = Sectors(256 * IEC::Mi / (SECTOR_SIZE as u64)); // = 256 MiB
 
+/// The type of every filesystem, as made by create_fs.
+const FS_TYPE: &'static str = SUPPORTED_FS_TYPES[0];
+
 #[derive(Debug)]
 pub struct StratFilesystem {
     fs_id: FilesystemUuid,
     name: String,
     thin_dev: ThinDev,
+    active: bool,
 }
 
 pub enum FilesystemStatus {
@@ -56,6 +63,7 @@ impl StratFilesystem {
             fs_id: fs_id,
             name: name.to_owned(),
             thin_dev: thin_dev,
+            active: true,
         }
     }
 
@@ -87,7 +95,7 @@ impl StratFilesystem {
                     // will fail due to duplicate UUID otherwise.
                     mount(Some(&thin_dev.devnode()),
                           tmp_dir.path(),
-                          Some("xfs"),
+                          Some(FS_TYPE),
                           MsFlags::empty(),
                           Some("nouuid"))?;
                     umount(tmp_dir.path())?;
@@ -108,6 +116,9 @@ impl StratFilesystem {
     /// check if filesystem is getting full and needs to be extended
     /// TODO: deal with the thindev in a Fail state.
     pub fn check(&mut self, dm: &DM) -> EngineResult<FilesystemStatus> {
+        if !self.active {
+            return Ok(FilesystemStatus::Good);
+        }
         match self.thin_dev.status(dm)? {
             ThinStatus::Good(_) => {
                 if let Some(mount_point) = self.get_mount_point()? {
@@ -159,9 +170,68 @@ impl StratFilesystem {
                                         self.devnode())))
     }
 
+    /// Set up the ThinDev under the filesystem if it is not already.
+    /// Return true if it was necessary to set it up.
+    pub fn activate(&mut self, dm: &DM, thin_pool: &ThinPoolDev) -> EngineResult<bool> {
+        if self.active {
+            return Ok(false);
+        }
+        let thin_dev = ThinDev::setup(dm,
+                                      self.thin_dev.name(),
+                                      None,
+                                      thin_pool,
+                                      self.thin_dev.id(),
+                                      self.thin_dev.size())?;
+        self.thin_dev = thin_dev;
+        self.active = true;
+        Ok(true)
+    }
+
+    /// Remove the ThinDev under the filesystem from the kernel, retaining
+    /// its data in the thin pool. Return true if it was necessary to remove
+    /// it. The filesystem must not be mounted.
+    pub fn deactivate(&mut self, dm: &DM) -> EngineResult<bool> {
+        if !self.active {
+            return Ok(false);
+        }
+        if is_mounted(self.thin_dev.device())? {
+            return Err(EngineError::Engine(ErrorEnum::Busy,
+                                           format!("filesystem {} is mounted", self.name)));
+        }
+        dm.device_remove(&DevId::Name(self.thin_dev.name()), DmFlags::empty())?;
+        self.active = false;
+        Ok(true)
+    }
+
+    /// Mount the filesystem at mount_point.
+    pub fn set_mount_point(&self, mount_point: &Path) -> EngineResult<()> {
+        if !self.active {
+            return Err(EngineError::Engine(ErrorEnum::Invalid,
+                                           format!("filesystem {} is not active", self.name)));
+        }
+        mount(Some(&self.devnode()),
+              mount_point,
+              Some(FS_TYPE),
+              MsFlags::empty(),
+              None as Option<&str>)?;
+        Ok(())
+    }
+
+    /// The IO which the ThinDev under the filesystem has completed.
+    pub fn io_stats(&self) -> EngineResult<IoStats> {
+        if !self.active {
+            return Err(EngineError::Engine(ErrorEnum::Invalid,
+                                           format!("filesystem {} is not active", self.name)));
+        }
+        io_stats(self.thin_dev.device())
+    }
+
     /// Tear down the filesystem.
     pub fn teardown(self, dm: &DM) -> EngineResult<()> {
-        Ok(self.thin_dev.teardown(dm)?)
+        if self.active {
+            self.thin_dev.teardown(dm)?;
+        }
+        Ok(())
     }
 
     /// Set the name of this filesystem to name.
@@ -170,7 +240,9 @@ impl StratFilesystem {
     }
 
     /// Destroy the filesystem.
-    pub fn destroy(self, dm: &DM, thin_pool: &ThinPoolDev) -> EngineResult<()> {
+    pub fn destroy(mut self, dm: &DM, thin_pool: &ThinPoolDev) -> EngineResult<()> {
+        // The ThinDev must be set up for the thin pool to delete it.
+        self.activate(dm, thin_pool)?;
         Ok(self.thin_dev.destroy(dm, thin_pool)?)
     }
 }
@@ -191,6 +263,14 @@ impl Filesystem for StratFilesystem {
     fn devnode(&self) -> PathBuf {
         self.thin_dev.devnode()
     }
+
+    fn is_active(&self) -> bool {
+        self.active
+    }
+
+    fn size(&self) -> Sectors {
+        self.thin_dev.size()
+    }
 }
 
 impl Recordable<FilesystemSave> for StratFilesystem {
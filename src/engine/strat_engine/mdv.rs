@@ -29,7 +29,8 @@ use super::util::create_fs;
 // TODO: Monitor fs size and extend linear and fs if needed
 // TODO: Document format of stuff on MDV in SWDD (currently ad-hoc)
 
-const DEV_PATH: &'static str = "/dev/stratis";
+/// The directory at which the metadata volumes of all pools are mounted.
+pub const DEV_PATH: &'static str = "/dev/stratis";
 
 const FILESYSTEM_DIR: &'static str = "filesystems";
 
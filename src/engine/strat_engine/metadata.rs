@@ -148,6 +148,11 @@ impl BDA {
         BDA_STATIC_HDR_SECTORS + self.header.mda_size + self.header.reserved_size
     }
 
+    /// The size a BDA written with an MDA of mda_size will occupy.
+    pub fn size_for(mda_size: Sectors) -> Sectors {
+        BDA_STATIC_HDR_SECTORS + mda_size + MDA_RESERVED_SECTORS
+    }
+
     /// The maximum size of variable length metadata that can be accommodated.
     pub fn max_data_size(&self) -> Sectors {
         self.regions.max_data_size()
@@ -15,7 +15,10 @@ use devicemapper::{Device, DM, Sectors, ThinPoolDev};
 
 use super::super::engine::{Filesystem, BlockDev, HasName, HasUuid, Pool};
 use super::super::errors::{EngineError, EngineResult, ErrorEnum};
-use super::super::types::{FilesystemUuid, PoolUuid, RenameAction, Redundancy};
+use super::super::types::{DevStatus, FilesystemUuid, IoStats, OpenMode, PoolUuid, RenameAction,
+                          Redundancy};
+use super::super::util::{filesystems_by_size, pool_config_hash, pool_device_status_counts,
+                         pool_status_detail, validate_filesystem_size, validate_low_watermark};
 
 use super::blockdevmgr::BlockDevMgr;
 use super::filesystem::StratFilesystem;
@@ -26,6 +29,11 @@ use super::thinpool::ThinPool;
 
 pub use super::thinpool::{DATA_BLOCK_SIZE, DATA_LOWATER, INITIAL_DATA_SIZE};
 
+/// The factor by which the logical capacity of a pool exceeds the physical
+/// space available for user data. It is the same for every pool, and can
+/// not be changed.
+const OVERPROVISION_FACTOR: u64 = 1;
+
 #[derive(Debug)]
 pub struct StratPool {
     name: String,
@@ -33,21 +41,25 @@ pub struct StratPool {
     block_devs: BlockDevMgr,
     redundancy: Redundancy,
     thin_pool: ThinPool,
+    hidden: bool,
 }
 
 impl StratPool {
     /// Initialize a Stratis Pool.
     /// 1. Initialize the block devices specified by paths.
     /// 2. Set up thinpool device to back filesystems.
+    /// The block devices are opened according to open_mode.
     pub fn initialize(name: &str,
                       dm: &DM,
                       paths: &[&Path],
                       redundancy: Redundancy,
-                      force: bool)
+                      force: bool,
+                      open_mode: OpenMode)
                       -> EngineResult<(StratPool, Vec<PathBuf>)> {
         let pool_uuid = Uuid::new_v4();
 
-        let mut block_mgr = BlockDevMgr::initialize(&pool_uuid, paths, MIN_MDA_SECTORS, force)?;
+        let mut block_mgr =
+            BlockDevMgr::initialize(&pool_uuid, paths, MIN_MDA_SECTORS, force, open_mode)?;
 
         let thinpool = ThinPool::new(pool_uuid, dm, DATA_BLOCK_SIZE, DATA_LOWATER, &mut block_mgr);
         let thinpool = match thinpool {
@@ -66,6 +78,7 @@ impl StratPool {
             block_devs: block_mgr,
             redundancy: redundancy,
             thin_pool: thinpool,
+            hidden: false,
         };
 
         pool.write_metadata()?;
@@ -94,6 +107,7 @@ impl StratPool {
                block_devs: bd_mgr,
                redundancy: Redundancy::NONE,
                thin_pool: thinpool,
+               hidden: false,
            })
     }
 
@@ -142,8 +156,15 @@ impl StratPool {
 
 impl Pool for StratPool {
     fn create_filesystems<'a, 'b>(&'a mut self,
-                                  specs: &[(&'b str, Option<Sectors>)])
+                                  specs: &[(&'b str, Option<Sectors>)],
+                                  require_redundancy: bool)
                                   -> EngineResult<Vec<(&'b str, FilesystemUuid)>> {
+        check_redundancy!(self; require_redundancy);
+
+        for size in specs.iter().filter_map(|&(_, size)| size) {
+            validate_filesystem_size(size)?;
+        }
+
         let names: HashMap<_, _> = HashMap::from_iter(specs.iter().map(|&tup| (tup.0, tup.1)));
         for name in names.keys() {
             if self.thin_pool
@@ -166,11 +187,17 @@ impl Pool for StratPool {
     }
 
     fn add_blockdevs(&mut self, paths: &[&Path], force: bool) -> EngineResult<Vec<PathBuf>> {
-        let bdev_paths = self.block_devs.add(&self.pool_uuid, paths, force)?;
+        let bdev_paths = self.block_devs
+            .add(&self.pool_uuid, paths, force, OpenMode::Exclusive)?;
         self.write_metadata()?;
         Ok(bdev_paths)
     }
 
+    fn evacuate_blockdev(&mut self, _path: &Path) -> EngineResult<()> {
+        let err_msg = "moving data between blockdevs is not supported";
+        Err(EngineError::Engine(ErrorEnum::Error, err_msg.into()))
+    }
+
     fn destroy(self) -> EngineResult<()> {
         self.thin_pool.teardown(&DM::new()?)?;
         self.block_devs.destroy_all()?;
@@ -198,6 +225,28 @@ impl Pool for StratPool {
         self.thin_pool.rename_filesystem(uuid, new_name)
     }
 
+    fn swap_filesystem_names(&mut self,
+                             uuid_a: &FilesystemUuid,
+                             uuid_b: &FilesystemUuid)
+                             -> EngineResult<()> {
+        self.thin_pool.swap_filesystem_names(uuid_a, uuid_b)
+    }
+
+    fn activate_filesystem(&mut self, uuid: &FilesystemUuid) -> EngineResult<bool> {
+        self.thin_pool.activate_filesystem(&DM::new()?, uuid)
+    }
+
+    fn deactivate_filesystem(&mut self, uuid: &FilesystemUuid) -> EngineResult<bool> {
+        self.thin_pool.deactivate_filesystem(&DM::new()?, uuid)
+    }
+
+    fn set_mount_point(&mut self, uuid: &FilesystemUuid, mount_point: &Path) -> EngineResult<()> {
+        match self.thin_pool.get_filesystem_by_uuid(uuid) {
+            Some(fs) => fs.set_mount_point(mount_point),
+            None => Err(EngineError::Engine(ErrorEnum::NotFound, uuid.simple().to_string())),
+        }
+    }
+
     fn rename(&mut self, name: &str) {
         self.name = name.to_owned();
     }
@@ -224,6 +273,25 @@ impl Pool for StratPool {
             .and_then(|v| Ok(v + self.block_devs.metadata_size()))
     }
 
+    fn total_usable_size(&self) -> Sectors {
+        let raw = self.block_devs.current_capacity() - self.block_devs.metadata_size();
+        let factor = self.redundancy.usable_factor(self.block_devs.blockdevs().len());
+        Sectors((*raw as f64 * factor) as u64)
+    }
+
+    fn largest_free_extent(&self) -> Sectors {
+        self.block_devs.largest_avail_extent()
+    }
+
+    fn redundancy_overhead(&self) -> Sectors {
+        let raw = self.block_devs.current_capacity() - self.block_devs.metadata_size();
+        raw - self.total_usable_size()
+    }
+
+    fn logical_capacity(&self) -> Sectors {
+        self.total_usable_size() * OVERPROVISION_FACTOR
+    }
+
     fn filesystems(&self) -> Vec<&Filesystem> {
         self.thin_pool.filesystems()
     }
@@ -231,6 +299,86 @@ impl Pool for StratPool {
     fn blockdevs(&self) -> Vec<&BlockDev> {
         self.block_devs.blockdevs()
     }
+
+    fn redundancy(&self) -> &Redundancy {
+        &self.redundancy
+    }
+
+    fn dedup_ratio(&self) -> Option<f64> {
+        None
+    }
+
+    fn config_hash(&self) -> String {
+        pool_config_hash(self, &self.redundancy)
+    }
+
+    fn status_detail(&self) -> String {
+        let mut problems = self.block_devs
+            .devnodes()
+            .iter()
+            .filter(|devnode| !devnode.exists())
+            .map(|devnode| format!("blockdev {} missing", devnode.display()))
+            .collect::<Vec<_>>();
+        problems.sort();
+        match DM::new() {
+            Ok(dm) => problems.extend(self.thin_pool.status_problem(&dm)),
+            Err(err) => problems.push(format!("devicemapper unavailable: {}", err)),
+        }
+        pool_status_detail(&problems)
+    }
+
+    fn hidden(&self) -> bool {
+        self.hidden
+    }
+
+    fn set_hidden(&mut self, hidden: bool) -> () {
+        self.hidden = hidden;
+    }
+
+    fn filesystem_io_stats(&self, name: &str) -> EngineResult<IoStats> {
+        self.thin_pool
+            .get_filesystem_by_name(name)
+            .ok_or_else(|| {
+                            EngineError::Engine(ErrorEnum::NotFound,
+                                                format!("no filesystem {}", name))
+                        })?
+            .io_stats()
+    }
+
+    fn filesystems_by_size(&self, min_size: Sectors, descending: bool) -> Vec<(String, Sectors)> {
+        filesystems_by_size(&self.filesystems(), min_size, descending)
+    }
+
+    fn data_low_watermark(&self) -> u8 {
+        self.thin_pool.data_low_watermark()
+    }
+
+    fn metadata_low_watermark(&self) -> u8 {
+        self.thin_pool.metadata_low_watermark()
+    }
+
+    fn set_data_low_watermark(&mut self, percent: u8) -> EngineResult<()> {
+        validate_low_watermark(percent)?;
+        self.thin_pool.set_data_low_watermark(percent);
+        Ok(())
+    }
+
+    fn set_metadata_low_watermark(&mut self, percent: u8) -> EngineResult<()> {
+        validate_low_watermark(percent)?;
+        self.thin_pool.set_metadata_low_watermark(percent);
+        Ok(())
+    }
+
+    fn device_status_counts(&self) -> HashMap<DevStatus, usize> {
+        pool_device_status_counts(self.block_devs
+                                      .devnodes()
+                                      .iter()
+                                      .map(|devnode| if devnode.exists() {
+                                               DevStatus::Online
+                                           } else {
+                                               DevStatus::Missing
+                                           }))
+    }
 }
 
 impl HasUuid for StratPool {
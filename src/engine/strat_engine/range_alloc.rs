@@ -191,6 +191,15 @@ impl RangeAllocator {
         self.used.values().cloned().sum()
     }
 
+    /// The length of the longest range of available sectors
+    pub fn largest_available(&self) -> Sectors {
+        self.avail_ranges()
+            .iter()
+            .map(|&(_, len)| len)
+            .max()
+            .unwrap_or(Sectors(0))
+    }
+
     /// Get a list of (offset, length) segments that are in use
     fn used_ranges(&self) -> Vec<(Sectors, Sectors)> {
         self.used.iter().map(|(k, v)| (*k, *v)).collect()
@@ -280,6 +289,25 @@ mod tests {
         assert_eq!(allocator.available(), Sectors(60));
     }
 
+    #[test]
+    /// Verify that when allocations are freed out of order, so that the
+    /// available sectors are no longer contiguous, the largest available
+    /// range is less than the total available.
+    fn test_allocator_largest_available() {
+        let mut allocator = RangeAllocator::new(Sectors(128), &[]).unwrap();
+        assert_eq!(allocator.largest_available(), Sectors(128));
+
+        let (_, segs) = allocator.request(Sectors(128));
+        assert_eq!(allocator.largest_available(), Sectors(0));
+
+        allocator.remove_ranges(&segs);
+        allocator
+            .insert_ranges(&[(Sectors(0), Sectors(32)), (Sectors(64), Sectors(32))])
+            .unwrap();
+        assert_eq!(allocator.available(), Sectors(64));
+        assert_eq!(allocator.largest_available(), Sectors(32));
+    }
+
     #[test]
     // Verify some proper functioning when allocator initialized with ranges.
     fn test_allocator_initialized_with_range() {
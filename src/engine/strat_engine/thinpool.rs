@@ -17,6 +17,7 @@ use super::super::engine::{Filesystem, HasName};
 use super::super::errors::{EngineError, EngineResult, ErrorEnum};
 use super::super::structures::Table;
 use super::super::types::{DevUuid, PoolUuid, FilesystemUuid, RenameAction};
+use super::super::util::{DEFAULT_LOW_WATERMARK, above_watermark, watermark_warning};
 
 use super::blockdevmgr::{BlockDevMgr, BlkDevSegment, map_to_dm};
 use super::device::wipe_sectors;
@@ -51,6 +52,11 @@ pub struct ThinPool {
     id_gen: ThinDevIdPool,
     filesystems: Table<StratFilesystem>,
     mdv: MetadataVol,
+    data_low_watermark: u8,
+    metadata_low_watermark: u8,
+    /// Whether a warning has been logged that metadata usage is above its
+    /// watermark, since it last was not.
+    metadata_warned: bool,
 }
 
 impl ThinPool {
@@ -120,6 +126,9 @@ impl ThinPool {
                id_gen: ThinDevIdPool::new_from_ids(&[]),
                filesystems: Table::default(),
                mdv: mdv,
+               data_low_watermark: DEFAULT_LOW_WATERMARK,
+               metadata_low_watermark: DEFAULT_LOW_WATERMARK,
+               metadata_warned: false,
            })
     }
 
@@ -237,6 +246,9 @@ impl ThinPool {
                id_gen: ThinDevIdPool::new_from_ids(&thin_ids),
                filesystems: fs_table,
                mdv: mdv,
+               data_low_watermark: DEFAULT_LOW_WATERMARK,
+               metadata_low_watermark: DEFAULT_LOW_WATERMARK,
+               metadata_warned: false,
            })
     }
 
@@ -255,6 +267,13 @@ impl ThinPool {
         INITIAL_MDV_SIZE
     }
 
+    /// The total space a newly created thinpool allocates from its
+    /// blockdevs, including the spare metadata device.
+    pub fn initial_size() -> Sectors {
+        ThinPool::initial_metadata_size() * 2u64 + ThinPool::initial_data_size() +
+        ThinPool::initial_mdv_size()
+    }
+
     /// Run status checks and take actions on the thinpool and its components.
     pub fn check(&mut self, dm: &DM, bd_mgr: &mut BlockDevMgr) -> EngineResult<()> {
         #![allow(match_same_arms)]
@@ -278,11 +297,22 @@ impl ThinPool {
                     }
                 }
 
-                if usage.used_meta > usage.total_meta - META_LOWATER {
-                    // TODO: Extend meta device
+                let meta_above = usage.used_meta > usage.total_meta - META_LOWATER ||
+                                 above_watermark(*usage.used_meta,
+                                                 *usage.total_meta,
+                                                 self.metadata_low_watermark);
+                // TODO: Extend meta device
+                let (warn, warned) = watermark_warning(meta_above, self.metadata_warned);
+                self.metadata_warned = warned;
+                if warn {
+                    warn!("thinpool {} metadata usage is above its low watermark",
+                          self.thin_pool.name());
                 }
 
-                if usage.used_data > usage.total_data - DATA_LOWATER {
+                if usage.used_data > usage.total_data - DATA_LOWATER ||
+                   above_watermark(*usage.used_data,
+                                   *usage.total_data,
+                                   self.data_low_watermark) {
                     // Request expansion of physical space allocated to the pool
                     match self.extend_thinpool(dm, usage.total_data, bd_mgr) {
                         #![allow(single_match)]
@@ -311,6 +341,27 @@ impl ThinPool {
         Ok(())
     }
 
+    /// The percentage of the data device in use above which it is extended.
+    pub fn data_low_watermark(&self) -> u8 {
+        self.data_low_watermark
+    }
+
+    /// The percentage of the metadata device in use above which a warning
+    /// is logged.
+    pub fn metadata_low_watermark(&self) -> u8 {
+        self.metadata_low_watermark
+    }
+
+    /// Set the data low watermark, a percentage.
+    pub fn set_data_low_watermark(&mut self, percent: u8) {
+        self.data_low_watermark = percent;
+    }
+
+    /// Set the metadata low watermark, a percentage.
+    pub fn set_metadata_low_watermark(&mut self, percent: u8) {
+        self.metadata_low_watermark = percent;
+    }
+
     /// Tear down the components managed here: filesystems, the MDV,
     /// and the actual thinpool device itself.
     pub fn teardown(self, dm: &DM) -> EngineResult<()> {
@@ -327,6 +378,25 @@ impl ThinPool {
         Ok(())
     }
 
+    /// Describe any problem with the thin pool device, or return None if
+    /// it is working normally.
+    pub fn status_problem(&self, dm: &DM) -> Option<String> {
+        match self.thin_pool.status(dm) {
+            Ok(dm::ThinPoolStatus::Good(ThinPoolWorkingStatus::Good, _)) => None,
+            Ok(dm::ThinPoolStatus::Good(ThinPoolWorkingStatus::ReadOnly, _)) => {
+                Some("thin pool is read-only".into())
+            }
+            Ok(dm::ThinPoolStatus::Good(ThinPoolWorkingStatus::OutOfSpace, _)) => {
+                Some("thin pool is out of data space".into())
+            }
+            Ok(dm::ThinPoolStatus::Good(ThinPoolWorkingStatus::NeedsCheck, _)) => {
+                Some("thin pool metadata needs checking".into())
+            }
+            Ok(dm::ThinPoolStatus::Fail) => Some("thin pool has failed".into()),
+            Err(err) => Some(format!("thin pool status unavailable: {}", err)),
+        }
+    }
+
     /// Get the devicemapper::ThinPoolDev for this pool. Used for testing.
     pub fn thinpooldev(&self) -> &ThinPoolDev {
         &self.thin_pool
@@ -435,7 +505,6 @@ impl ThinPool {
         self.filesystems.get_mut_by_uuid(uuid)
     }
 
-    #[allow(dead_code)]
     pub fn get_filesystem_by_name(&self, name: &str) -> Option<&StratFilesystem> {
         self.filesystems.get_by_name(name)
     }
@@ -517,6 +586,70 @@ impl ThinPool {
         Ok(())
     }
 
+    /// Exchange the names of two filesystems within the thin pool.
+    /// If the new names can not be saved, the old names are restored.
+    pub fn swap_filesystem_names(&mut self,
+                                 uuid_a: &FilesystemUuid,
+                                 uuid_b: &FilesystemUuid)
+                                 -> EngineResult<()> {
+        for uuid in &[uuid_a, uuid_b] {
+            if self.filesystems.get_by_uuid(uuid).is_none() {
+                return Err(EngineError::Engine(ErrorEnum::NotFound, uuid.simple().to_string()));
+            }
+        }
+        if uuid_a == uuid_b {
+            return Ok(());
+        }
+
+        let mut filesystem_a = self.filesystems
+            .remove_by_uuid(uuid_a)
+            .expect("Must succeed since self.filesystems.get_by_uuid() returned a value");
+        let mut filesystem_b = self.filesystems
+            .remove_by_uuid(uuid_b)
+            .expect("Must succeed since self.filesystems.get_by_uuid() returned a value");
+
+        let name_a = filesystem_a.name().to_owned();
+        let name_b = filesystem_b.name().to_owned();
+        filesystem_a.rename(&name_b);
+        filesystem_b.rename(&name_a);
+
+        let result = self.mdv
+            .save_fs(&filesystem_a)
+            .and_then(|_| self.mdv.save_fs(&filesystem_b));
+        if result.is_err() {
+            filesystem_a.rename(&name_a);
+            filesystem_b.rename(&name_b);
+            // TODO: If the first save succeeded, the MDV now records
+            // filesystem_a under name_b until the next successful save.
+            let _ = self.mdv
+                .save_fs(&filesystem_a)
+                .and_then(|_| self.mdv.save_fs(&filesystem_b));
+        }
+
+        self.filesystems.insert(filesystem_a);
+        self.filesystems.insert(filesystem_b);
+        result
+    }
+
+    /// Activate a filesystem within the thin pool.
+    pub fn activate_filesystem(&mut self, dm: &DM, uuid: &FilesystemUuid) -> EngineResult<bool> {
+        match self.filesystems.get_mut_by_uuid(uuid) {
+            Some(fs) => fs.activate(dm, &self.thin_pool),
+            None => Err(EngineError::Engine(ErrorEnum::NotFound, uuid.simple().to_string())),
+        }
+    }
+
+    /// Deactivate a filesystem within the thin pool.
+    pub fn deactivate_filesystem(&mut self,
+                                 dm: &DM,
+                                 uuid: &FilesystemUuid)
+                                 -> EngineResult<bool> {
+        match self.filesystems.get_mut_by_uuid(uuid) {
+            Some(fs) => fs.deactivate(dm),
+            None => Err(EngineError::Engine(ErrorEnum::NotFound, uuid.simple().to_string())),
+        }
+    }
+
     /// Rename a filesystem within the thin pool.
     pub fn rename_filesystem(&mut self,
                              uuid: &FilesystemUuid,
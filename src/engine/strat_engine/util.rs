@@ -58,3 +58,23 @@ pub fn set_uuid(devnode: &Path, uuid: Uuid) -> EngineResult<()> {
         Err(EngineError::Engine(ErrorEnum::Error, err_msg))
     }
 }
+
+/// Use blkid to identify the filesystem or partition table on devnode.
+/// Returns None if blkid finds neither.
+pub fn probe_signature(devnode: &Path) -> EngineResult<Option<String>> {
+    let output = Command::new("blkid")
+        .arg("-p")
+        .arg("-o")
+        .arg("export")
+        .arg(devnode)
+        .output()?;
+    let output = String::from_utf8_lossy(&output.stdout);
+    let value = |key: &str| {
+        let prefix = format!("{}=", key);
+        output
+            .lines()
+            .find(|line| line.starts_with(&prefix))
+            .map(|line| line[prefix.len()..].to_owned())
+    };
+    Ok(value("TYPE").or_else(|| value("PTTYPE")))
+}
@@ -2,8 +2,17 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::SystemTime;
+
 use uuid::Uuid;
 
+use devicemapper::{Bytes, Sectors};
+
+use super::errors::{EngineError, ErrorEnum};
+
 pub type DevUuid = Uuid;
 pub type FilesystemUuid = Uuid;
 pub type PoolUuid = Uuid;
@@ -15,6 +24,83 @@ pub enum RenameAction {
     Renamed,
 }
 
+/// Which backend an engine is.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EngineKind {
+    /// The simulator, which touches no devices.
+    Sim,
+    /// The real engine, which manages actual block devices.
+    Real,
+}
+
+impl fmt::Display for EngineKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            EngineKind::Sim => write!(f, "sim"),
+            EngineKind::Real => write!(f, "real"),
+        }
+    }
+}
+
+/// A record of a pool which has been destroyed.
+#[derive(Clone, Debug)]
+pub struct DestroyedPoolInfo {
+    pub name: String,
+    pub uuid: PoolUuid,
+    /// The device nodes of the blockdevs which the pool occupied.
+    pub devnodes: Vec<PathBuf>,
+    pub destroyed: SystemTime,
+}
+
+/// A description of a pool, to which the engine can make the pool of that
+/// name conform.
+#[derive(Clone, Debug)]
+pub struct PoolSpec {
+    pub name: String,
+    pub devices: Vec<PathBuf>,
+    /// The redundancy code, as for Engine::create_pool.
+    pub redundancy: Option<u16>,
+    /// The names of the pool's filesystems.
+    pub filesystems: Vec<String>,
+    /// Whether filesystems which the spec does not name may be destroyed.
+    pub allow_destroy: bool,
+}
+
+/// Counts of the IO which a filesystem's device has completed.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct IoStats {
+    pub read_bytes: Bytes,
+    pub write_bytes: Bytes,
+    pub read_ops: u64,
+    pub write_ops: u64,
+}
+
+/// How the engine opens a block device.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OpenMode {
+    /// Open with O_EXCL, so that the open fails if the device is held by
+    /// some other process, e.g., because it is mounted.
+    Exclusive,
+    /// Open without O_EXCL. Useful for tests and for inspecting devices.
+    Shared,
+}
+
+custom_derive! {
+    #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, EnumDisplay)]
+    /// The state of a blockdev belonging to a pool.
+    pub enum DevStatus {
+        /// The device is present and in use.
+        Online,
+        /// The device is present, but has failed and holds no usable data.
+        Failed,
+        /// The device belongs to the pool but can not be found.
+        Missing,
+        /// The device is present and held in reserve, to replace a device
+        /// which fails or goes missing.
+        Spare,
+    }
+}
+
 /// Redundancy classifications which the engine allows for pools.
 custom_derive! {
     #[derive(Debug, Eq, PartialEq, EnumDisplay,
@@ -23,6 +109,83 @@ custom_derive! {
     /// Redundancy specification for a pool.
     pub enum Redundancy {
         NONE,
+        RAID1,
+        RAID5,
+    }
+}
+
+impl Redundancy {
+    /// Whether data survives the loss of a device at this redundancy.
+    pub fn is_redundant(&self) -> bool {
+        match *self {
+            Redundancy::NONE => false,
+            Redundancy::RAID1 | Redundancy::RAID5 => true,
+        }
+    }
+
+    /// The fewest devices a pool may have at this redundancy. A pool
+    /// without redundancy may have no devices at all.
+    pub fn min_devices(&self) -> usize {
+        match *self {
+            Redundancy::NONE => 0,
+            Redundancy::RAID1 => 2,
+            Redundancy::RAID5 => 3,
+        }
+    }
+
+    /// The fraction of the raw capacity of num_devs devices that is
+    /// available for data at this redundancy.
+    pub fn usable_factor(&self, num_devs: usize) -> f64 {
+        match *self {
+            Redundancy::NONE => 1.0,
+            Redundancy::RAID1 => 0.5,
+            Redundancy::RAID5 => {
+                if num_devs == 0 {
+                    0.0
+                } else {
+                    (num_devs - 1) as f64 / num_devs as f64
+                }
+            }
+        }
+    }
+
+    /// The space available for data on devices of the given sizes at this
+    /// redundancy. Each device of a RAID array contributes only as much
+    /// space as the smallest of them; too few devices for this redundancy
+    /// have no usable space at all.
+    pub fn usable_size(&self, sizes: &[Sectors]) -> Sectors {
+        let smallest = match sizes.iter().min() {
+            Some(smallest) => *smallest,
+            None => return Sectors(0),
+        };
+        if sizes.len() < self.min_devices() {
+            return Sectors(0);
+        }
+        let num_devs = sizes.len() as u64;
+        match *self {
+            Redundancy::NONE => sizes.iter().cloned().sum(),
+            Redundancy::RAID1 => smallest * num_devs / 2u64,
+            Redundancy::RAID5 => smallest * (num_devs - 1),
+        }
+    }
+}
+
+/// Parse a redundancy from its name, ignoring case. Common alternatives to
+/// the names of the constructors are also accepted, e.g., "mirror" or "1"
+/// for RAID1.
+impl FromStr for Redundancy {
+    type Err = EngineError;
+
+    fn from_str(s: &str) -> Result<Redundancy, EngineError> {
+        match s.to_lowercase().as_str() {
+            "none" | "0" => Ok(Redundancy::NONE),
+            "raid1" | "1" | "mirror" => Ok(Redundancy::RAID1),
+            "raid5" | "5" => Ok(Redundancy::RAID5),
+            _ => {
+                let message = format!("{} does not name any redundancy", s);
+                Err(EngineError::Engine(ErrorEnum::Error, message))
+            }
+        }
     }
 }
 
@@ -32,3 +195,49 @@ impl From<Redundancy> for u16 {
         r as u16
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use devicemapper::{IEC, Sectors};
+
+    use super::super::errors::{EngineError, ErrorEnum};
+
+    use super::Redundancy;
+
+    #[test]
+    /// Names of RAID1 in any case parse to RAID1; other names do not parse.
+    fn redundancy_from_str() {
+        for name in &["RAID1", "raid1", "mirror", "1"] {
+            assert_eq!(Redundancy::from_str(name).unwrap(), Redundancy::RAID1);
+        }
+        assert!(match Redundancy::from_str("bogus") {
+                    Err(EngineError::Engine(ErrorEnum::Error, _)) => true,
+                    _ => false,
+                });
+    }
+
+    #[test]
+    /// One device supports only NONE; three support every redundancy.
+    fn redundancy_min_devices() {
+        let usable = |num_devs| {
+            Redundancy::iter_variants()
+                .filter(|r| r.min_devices() <= num_devs)
+                .collect::<Vec<Redundancy>>()
+        };
+        assert_eq!(usable(1), vec![Redundancy::NONE]);
+        assert_eq!(usable(3),
+                   vec![Redundancy::NONE, Redundancy::RAID1, Redundancy::RAID5]);
+    }
+
+    #[test]
+    /// A RAID array is limited by its smallest device; a linear one is not.
+    fn redundancy_usable_size() {
+        let sizes = [Sectors(2 * IEC::Mi), Sectors(4 * IEC::Mi), Sectors(4 * IEC::Mi)];
+        assert_eq!(Redundancy::NONE.usable_size(&sizes), Sectors(10 * IEC::Mi));
+        assert_eq!(Redundancy::RAID1.usable_size(&sizes), Sectors(3 * IEC::Mi));
+        assert_eq!(Redundancy::RAID5.usable_size(&sizes), Sectors(4 * IEC::Mi));
+        assert_eq!(Redundancy::RAID5.usable_size(&sizes[..2]), Sectors(0));
+    }
+}
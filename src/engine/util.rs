@@ -0,0 +1,547 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+// Utilities shared by all engines.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use sha2::{Digest, Sha256};
+
+use devicemapper::{SECTOR_SIZE, Sectors};
+
+use super::engine::{Engine, Filesystem, Pool};
+use super::errors::{EngineError, EngineResult, ErrorEnum};
+use super::types::{DestroyedPoolInfo, DevStatus, FilesystemUuid, PoolSpec, Redundancy};
+
+/// The filesystem types which may be created on a Stratis filesystem.
+pub const SUPPORTED_FS_TYPES: &'static [&'static str] = &["xfs"];
+
+/// The number of destroyed pools an engine remembers.
+pub const MAX_DESTROYED_POOLS: usize = 16;
+
+/// Verify that fs_type is one of SUPPORTED_FS_TYPES.
+pub fn validate_fs_type(fs_type: &str) -> EngineResult<()> {
+    if SUPPORTED_FS_TYPES.contains(&fs_type) {
+        Ok(())
+    } else {
+        let err_msg = format!("filesystem type {} is not supported", fs_type);
+        Err(EngineError::Engine(ErrorEnum::Invalid, err_msg))
+    }
+}
+
+/// Verify that a filesystem of the given size can be accounted for, i.e.,
+/// that its size in bytes does not overflow.
+pub fn validate_filesystem_size(size: Sectors) -> EngineResult<()> {
+    match size.checked_mul(SECTOR_SIZE as u64) {
+        Some(_) => Ok(()),
+        None => Err(EngineError::Engine(ErrorEnum::Invalid, "size too large".into())),
+    }
+}
+
+/// The default percentage of a pool's data or metadata space in use above
+/// which the engine acts to provide more.
+pub const DEFAULT_LOW_WATERMARK: u8 = 90;
+
+/// Verify that a low watermark is a percentage other than 0. At 0, any
+/// usage at all would be above the watermark.
+pub fn validate_low_watermark(percent: u8) -> EngineResult<()> {
+    if percent == 0 || percent > 100 {
+        let err_msg = format!("watermark {} is not a percentage from 1 to 100", percent);
+        return Err(EngineError::Engine(ErrorEnum::Invalid, err_msg));
+    }
+    Ok(())
+}
+
+/// Whether more than percent of total is used.
+pub fn above_watermark(used: u64, total: u64, percent: u8) -> bool {
+    used * 100 > total * u64::from(percent)
+}
+
+/// Whether to warn that usage is above a watermark, given whether it is
+/// above now and whether a warning was already given since it last was not.
+/// Returns that, and whether a warning has then been given since usage was
+/// last below the watermark, which is to be passed to the next check.
+pub fn watermark_warning(above: bool, warned: bool) -> (bool, bool) {
+    (above && !warned, above)
+}
+
+/// The percentage of the smallest device's size by which the sizes of
+/// devices may differ and still be considered uniform.
+const UNIFORM_SIZE_TOLERANCE_PERCENT: u64 = 1;
+
+/// Verify that the given device sizes differ by no more than
+/// UNIFORM_SIZE_TOLERANCE_PERCENT.
+pub fn validate_uniform_sizes(sizes: &[Sectors]) -> EngineResult<()> {
+    let (min_size, max_size) = match (sizes.iter().min(), sizes.iter().max()) {
+        (Some(min_size), Some(max_size)) => (**min_size, **max_size),
+        _ => return Ok(()),
+    };
+    if (max_size - min_size) * 100 > min_size * UNIFORM_SIZE_TOLERANCE_PERCENT {
+        let err_msg = format!("device sizes range from {} to {} sectors, more than {}% apart",
+                              min_size,
+                              max_size,
+                              UNIFORM_SIZE_TOLERANCE_PERCENT);
+        return Err(EngineError::Engine(ErrorEnum::DeviceSizeMismatch, err_msg));
+    }
+    Ok(())
+}
+
+/// Verify that device_count devices are enough for a pool with the given
+/// redundancy.
+pub fn validate_device_count(redundancy: &Redundancy, device_count: usize) -> EngineResult<()> {
+    if device_count < redundancy.min_devices() {
+        let err_msg = format!("redundancy {} requires at least {} devices, but {} were given",
+                              redundancy,
+                              redundancy.min_devices(),
+                              device_count);
+        return Err(EngineError::Engine(ErrorEnum::Invalid, err_msg));
+    }
+    Ok(())
+}
+
+/// The smallest size each of device_count equally sized devices may have
+/// and still hold a pool at redundancy with volume_size of usable space.
+/// pool_overhead is space the pool allocates for its own use, which is
+/// subject to the redundancy like any data; device_overhead is space each
+/// device sets aside for its own metadata.
+pub fn min_device_size(volume_size: Sectors,
+                       redundancy: &Redundancy,
+                       device_count: usize,
+                       pool_overhead: Sectors,
+                       device_overhead: Sectors)
+                       -> EngineResult<Sectors> {
+    let too_large = || EngineError::Engine(ErrorEnum::Invalid, "size too large".into());
+
+    // Each device must have at least the data size times numerator, divided
+    // by denominator, rounded up.
+    let num_devs = device_count as u64;
+    let (numerator, denominator) = match *redundancy {
+        Redundancy::NONE => (1, num_devs),
+        Redundancy::RAID1 => (2, num_devs),
+        Redundancy::RAID5 => (1, num_devs.saturating_sub(1)),
+    };
+    if denominator == 0 {
+        let err_msg = format!("{} devices can not hold a pool with redundancy {}",
+                              device_count,
+                              redundancy);
+        return Err(EngineError::Engine(ErrorEnum::Invalid, err_msg));
+    }
+
+    let data = volume_size
+        .checked_add(pool_overhead)
+        .and_then(|data| data.checked_mul(numerator))
+        .ok_or_else(too_large)?;
+    let per_device = data / denominator + if data % denominator == 0 { 0 } else { 1 };
+    Sectors(per_device)
+        .checked_add(device_overhead)
+        .ok_or_else(too_large)
+}
+
+/// The path which path names, with symlinks resolved, or path itself if
+/// it can not be resolved, e.g., because it does not exist.
+fn canonical_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Make the pool named by spec conform to it, as described for
+/// Engine::apply_pool_spec.
+pub fn apply_pool_spec(engine: &mut Engine, spec: &PoolSpec) -> EngineResult<Vec<String>> {
+    let mut changes = Vec::new();
+    match apply_pool_spec_changes(engine, spec, &mut changes) {
+        Ok(()) => Ok(changes),
+        Err(err) => {
+            if changes.is_empty() {
+                return Err(err);
+            }
+            let (kind, msg) = match err {
+                EngineError::Engine(kind, msg) => (kind, msg),
+                err => (ErrorEnum::Error, format!("{}", err)),
+            };
+            let msg = format!("{}; changes made before the failure: {}",
+                              msg,
+                              changes.join(", "));
+            Err(EngineError::Engine(kind, msg))
+        }
+    }
+}
+
+/// Make the pool named by spec conform to it, pushing a description of each
+/// change onto changes as it is made. Everything which can be checked
+/// without making a change is checked before any change is made.
+fn apply_pool_spec_changes(engine: &mut Engine,
+                           spec: &PoolSpec,
+                           changes: &mut Vec<String>)
+                           -> EngineResult<()> {
+    let devices = spec.devices
+        .iter()
+        .map(|d| d.as_path())
+        .collect::<Vec<&Path>>();
+
+    let existing = engine
+        .pools()
+        .iter()
+        .find(|p| p.name() == spec.name)
+        .map(|p| *p.uuid());
+    let uuid = match existing {
+        Some(uuid) => uuid,
+        None => {
+            let (uuid, _) =
+                engine.create_pool(&spec.name, &devices, spec.redundancy, false, false, false)?;
+            changes.push(format!("created pool {}", spec.name));
+            uuid
+        }
+    };
+    let pool = engine
+        .get_mut_pool(&uuid)
+        .expect("pool was found or created above");
+
+    let redundancy = spec.redundancy;
+    let redundancy = calculate_redundancy!(redundancy);
+    if *pool.redundancy() != redundancy {
+        let err_msg = format!("pool {} has redundancy {}, not {}",
+                              spec.name,
+                              pool.redundancy(),
+                              redundancy);
+        return Err(EngineError::Engine(ErrorEnum::Invalid, err_msg));
+    }
+
+    // Compare devices by the paths they resolve to, so that a spec may name
+    // a device by any of its links.
+    let spec_devnodes = devices
+        .iter()
+        .map(|d| canonical_path(d))
+        .collect::<Vec<PathBuf>>();
+    let devnodes = pool.blockdevs()
+        .iter()
+        .map(|bd| canonical_path(&bd.devnode()))
+        .collect::<Vec<PathBuf>>();
+    if let Some(devnode) = devnodes.iter().find(|d| !spec_devnodes.contains(d)) {
+        let err_msg = format!("blockdev {} can not be removed from pool {}",
+                              devnode.display(),
+                              spec.name);
+        return Err(EngineError::Engine(ErrorEnum::Invalid, err_msg));
+    }
+
+    let to_destroy = pool.filesystems()
+        .iter()
+        .filter(|fs| !spec.filesystems.iter().any(|name| name == fs.name()))
+        .map(|fs| (fs.name().to_owned(), *fs.uuid()))
+        .collect::<Vec<(String, FilesystemUuid)>>();
+    if !to_destroy.is_empty() && !spec.allow_destroy {
+        let err_msg = format!("pool {} has filesystems {} which the spec does not name",
+                              spec.name,
+                              to_destroy
+                                  .iter()
+                                  .map(|&(ref name, _)| name.as_str())
+                                  .collect::<Vec<&str>>()
+                                  .join(", "));
+        return Err(EngineError::Engine(ErrorEnum::Invalid, err_msg));
+    }
+
+    let to_add = devices
+        .iter()
+        .zip(spec_devnodes.iter())
+        .filter(|&(_, spec_devnode)| !devnodes.contains(spec_devnode))
+        .map(|(d, _)| *d)
+        .collect::<Vec<&Path>>();
+    if !to_add.is_empty() {
+        for devnode in pool.add_blockdevs(&to_add, false)? {
+            changes.push(format!("added blockdev {}", devnode.display()));
+        }
+    }
+
+    let to_create = {
+        let fs_names = pool.filesystems()
+            .iter()
+            .map(|fs| fs.name().to_owned())
+            .collect::<Vec<String>>();
+        spec.filesystems
+            .iter()
+            .filter(|name| !fs_names.contains(name))
+            .map(|name| (name.as_str(), None))
+            .collect::<Vec<_>>()
+    };
+    if !to_create.is_empty() {
+        for (name, _) in pool.create_filesystems(&to_create, false)? {
+            changes.push(format!("created filesystem {}", name));
+        }
+    }
+
+    if !to_destroy.is_empty() {
+        let uuids = to_destroy
+            .iter()
+            .map(|&(_, ref uuid)| uuid)
+            .collect::<Vec<&FilesystemUuid>>();
+        pool.destroy_filesystems(&uuids)?;
+        for (name, _) in to_destroy {
+            changes.push(format!("destroyed filesystem {}", name));
+        }
+    }
+
+    Ok(())
+}
+
+/// The names and sizes of those filesystems of at least min_size, sorted
+/// by size, largest first if descending is true. Filesystems of equal size
+/// are sorted by name.
+pub fn filesystems_by_size(filesystems: &[&Filesystem],
+                           min_size: Sectors,
+                           descending: bool)
+                           -> Vec<(String, Sectors)> {
+    let mut sizes = filesystems
+        .iter()
+        .filter(|fs| fs.size() >= min_size)
+        .map(|fs| (fs.name().to_owned(), fs.size()))
+        .collect::<Vec<_>>();
+    sizes.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+    if descending {
+        sizes.reverse();
+    }
+    sizes
+}
+
+/// Combine descriptions of the problems a pool has into a single
+/// explanation of its state.
+pub fn pool_status_detail(problems: &[String]) -> String {
+    if problems.is_empty() {
+        "pool is running normally".into()
+    } else {
+        problems.join("; ")
+    }
+}
+
+/// Record in destroyed that pool is being destroyed, forgetting the
+/// earliest record if more than MAX_DESTROYED_POOLS would be kept.
+pub fn record_destroyed_pool(destroyed: &mut VecDeque<DestroyedPoolInfo>, pool: &Pool) {
+    if destroyed.len() >= MAX_DESTROYED_POOLS {
+        destroyed.pop_front();
+    }
+    destroyed.push_back(DestroyedPoolInfo {
+                            name: pool.name().to_owned(),
+                            uuid: *pool.uuid(),
+                            devnodes: pool.blockdevs().iter().map(|bd| bd.devnode()).collect(),
+                            destroyed: SystemTime::now(),
+                        });
+}
+
+/// Count the blockdevs of a pool having each of the given statuses.
+pub fn pool_device_status_counts<I>(statuses: I) -> HashMap<DevStatus, usize>
+    where I: Iterator<Item = DevStatus>
+{
+    let mut counts = HashMap::new();
+    for status in statuses {
+        *counts.entry(status).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// The names of the filesystems in pools which would lose data if the
+/// blockdev at path were lost. Since a pool spreads the data of each of its
+/// filesystems over all its blockdevs, these are all the filesystems in
+/// the pool containing the blockdev, unless that pool is redundant.
+pub fn filesystems_affected_by_device(pools: &[&Pool], path: &Path) -> EngineResult<Vec<String>> {
+    let pool = pools
+        .iter()
+        .find(|p| p.blockdevs().iter().any(|bd| bd.devnode() == path))
+        .ok_or_else(|| {
+                        EngineError::Engine(ErrorEnum::NotFound,
+                                            format!("no pool contains blockdev {}",
+                                                    path.display()))
+                    })?;
+    if pool.redundancy().is_redundant() {
+        return Ok(vec![]);
+    }
+    Ok(pool.filesystems()
+           .iter()
+           .map(|fs| fs.name().to_owned())
+           .collect())
+}
+
+/// Escape a Prometheus label value.
+fn prometheus_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// The number of bytes in sectors, as a Prometheus sample value. A
+/// float is used since the size of a pool in bytes may exceed u64.
+fn prometheus_bytes(sectors: Sectors) -> String {
+    format!("{}", *sectors as f64 * SECTOR_SIZE as f64)
+}
+
+/// Render gauges describing pools in the Prometheus text exposition
+/// format. Each per-pool sample is labelled with the pool's name and uuid.
+/// A pool's status is 0 if all its blockdevs are online, otherwise 1.
+pub fn pools_metrics_prometheus(pools: &[&Pool]) -> String {
+    let gauges: [(&str, &str, fn(&Pool) -> Option<String>); 4] =
+        [("stratis_pool_total_bytes",
+          "Total physical size of the pool in bytes.",
+          |p| Some(prometheus_bytes(p.total_physical_size()))),
+         ("stratis_pool_used_bytes",
+          "Physical space in use in the pool in bytes.",
+          |p| p.total_physical_used().ok().map(prometheus_bytes)),
+         ("stratis_pool_fill_percent",
+          "Percentage of the physical size of the pool in use.",
+          |p| {
+              let total = *p.total_physical_size();
+              p.total_physical_used()
+                  .ok()
+                  .filter(|_| total != 0)
+                  .map(|u| format!("{}", *u as f64 * 100.0 / total as f64))
+          }),
+         ("stratis_pool_status",
+          "Status of the pool: 0 if all blockdevs are online, otherwise 1.",
+          |p| {
+              let degraded = p.device_status_counts()
+                  .keys()
+                  .any(|s| *s == DevStatus::Failed || *s == DevStatus::Missing);
+              Some(if degraded { "1" } else { "0" }.into())
+          })];
+
+    let mut output = String::new();
+    output.push_str("# HELP stratis_pools Number of pools.\n");
+    output.push_str("# TYPE stratis_pools gauge\n");
+    output.push_str(&format!("stratis_pools {}\n", pools.len()));
+    for &(name, help, value) in &gauges {
+        output.push_str(&format!("# HELP {} {}\n", name, help));
+        output.push_str(&format!("# TYPE {} gauge\n", name));
+        for pool in pools {
+            if let Some(value) = value(*pool) {
+                output.push_str(&format!("{}{{pool=\"{}\",uuid=\"{}\"}} {}\n",
+                                         name,
+                                         prometheus_label(pool.name()),
+                                         pool.uuid().simple(),
+                                         value));
+            }
+        }
+    }
+    output
+}
+
+/// Compute a hash of the configuration of pool, which has the given
+/// redundancy: its blockdevs, and its filesystems with their sizes. The
+/// hash does not depend on the pool's name, nor on the order in which its
+/// blockdevs or filesystems were added. It is the hex-encoded SHA-256
+/// digest of a canonical description of the configuration.
+pub fn pool_config_hash(pool: &Pool, redundancy: &Redundancy) -> String {
+    let mut devnodes = pool.blockdevs()
+        .iter()
+        .map(|bd| bd.devnode())
+        .collect::<Vec<_>>();
+    devnodes.sort();
+
+    let mut fs_specs = pool.filesystems()
+        .iter()
+        .map(|fs| (fs.name().to_owned(), fs.size()))
+        .collect::<Vec<_>>();
+    fs_specs.sort();
+
+    let mut config = format!("redundancy={}\n", redundancy);
+    for devnode in devnodes {
+        config.push_str(&format!("blockdev={}\n", devnode.display()));
+    }
+    for (name, size) in fs_specs {
+        config.push_str(&format!("filesystem={} size={}\n", name, *size));
+    }
+    Sha256::digest(config.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use devicemapper::{IEC, Sectors};
+
+    use super::super::errors::{EngineError, ErrorEnum};
+    use super::super::types::Redundancy;
+
+    use super::{above_watermark, min_device_size, validate_uniform_sizes, watermark_warning};
+
+    #[test]
+    /// Devices of 1 GiB and 2 GiB are not uniform, two 1 GiB devices are.
+    fn uniform_sizes() {
+        let one_gib = Sectors(2 * IEC::Mi);
+        let two_gib = Sectors(4 * IEC::Mi);
+        assert!(match validate_uniform_sizes(&[one_gib, two_gib]) {
+                    Err(EngineError::Engine(ErrorEnum::DeviceSizeMismatch, _)) => true,
+                    _ => false,
+                });
+        assert!(validate_uniform_sizes(&[one_gib, one_gib]).is_ok());
+    }
+
+    #[test]
+    /// Each device of a two device RAID1 pool must hold a full copy of the
+    /// volume, in addition to its own metadata.
+    fn min_device_size_raid1() {
+        let volume_size = Sectors(2 * IEC::Mi);
+        let device_overhead = Sectors(IEC::Ki);
+        let size = min_device_size(volume_size,
+                                   &Redundancy::RAID1,
+                                   2,
+                                   Sectors(0),
+                                   device_overhead)
+                .unwrap();
+        assert!(size >= volume_size + device_overhead);
+        assert!(min_device_size(volume_size, &Redundancy::RAID5, 0, Sectors(0), Sectors(0))
+                    .is_err());
+    }
+
+    #[test]
+    /// A volume size so large that the device size overflows is rejected,
+    /// rather than wrapping.
+    fn min_device_size_too_large() {
+        for redundancy in Redundancy::iter_variants() {
+            assert!(match min_device_size(Sectors(u64::max_value()),
+                                          &redundancy,
+                                          3,
+                                          Sectors(IEC::Ki),
+                                          Sectors(IEC::Ki)) {
+                        Err(EngineError::Engine(ErrorEnum::Invalid, _)) => true,
+                        _ => false,
+                    });
+        }
+    }
+
+    #[test]
+    /// The minimum device size is exact for sizes which floating point
+    /// arithmetic can not represent.
+    fn min_device_size_precise() {
+        let volume_size = Sectors(1 << 60);
+        let size = min_device_size(volume_size + Sectors(1),
+                                   &Redundancy::NONE,
+                                   1,
+                                   Sectors(0),
+                                   Sectors(0))
+                .unwrap();
+        assert_eq!(size, volume_size + Sectors(1));
+    }
+
+    #[test]
+    /// Usage is above a watermark only once it exceeds that percentage.
+    fn watermark_crossed() {
+        assert!(!above_watermark(90, 100, 90));
+        assert!(above_watermark(91, 100, 90));
+        assert!(!above_watermark(100, 100, 100));
+    }
+
+    #[test]
+    /// A warning is given once when usage rises above a watermark, and again
+    /// only after usage has fallen below it.
+    fn watermark_warning_once() {
+        let (warn, warned) = watermark_warning(true, false);
+        assert!(warn);
+        let (warn, warned) = watermark_warning(true, warned);
+        assert!(!warn);
+        let (warn, warned) = watermark_warning(false, warned);
+        assert!(!warn);
+        assert!(!warned);
+        assert_eq!(watermark_warning(true, warned), (true, true));
+    }
+}
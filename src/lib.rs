@@ -8,6 +8,7 @@ extern crate devicemapper;
 #[macro_use]
 extern crate nix;
 extern crate crc;
+extern crate sha2;
 extern crate byteorder;
 extern crate uuid;
 extern crate chrono;
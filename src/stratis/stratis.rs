@@ -4,3 +4,17 @@
 
 
 pub const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+/// The git commit from which stratisd was built, or "unknown".
+pub const GIT_COMMIT: &'static str = env!("STRATIS_GIT_COMMIT");
+
+/// The version of rustc with which stratisd was built, or "unknown".
+pub const RUSTC_VERSION: &'static str = env!("STRATIS_RUSTC_VERSION");
+
+/// The cargo features enabled in this build.
+pub fn enabled_features() -> Vec<&'static str> {
+    env!("STRATIS_FEATURES")
+        .split(',')
+        .filter(|f| !f.is_empty())
+        .collect()
+}
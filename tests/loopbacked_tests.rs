@@ -28,6 +28,7 @@ use util::blockdev_tests::test_blockdevmgr_used;
 use util::blockdev_tests::test_force_flag_dirty;
 use util::blockdev_tests::test_force_flag_stratis;
 use util::blockdev_tests::test_pool_blockdevs;
+use util::blockdev_tests::test_pool_busy_device;
 use util::dm_tests::test_thinpool_device;
 use util::dm_tests::test_linear_device;
 use util::filesystem_tests::test_xfs_expand;
@@ -178,6 +179,11 @@ pub fn loop_test_pool_blockdevs() {
     test_with_spec(DeviceLimits::Range(2, 3), test_pool_blockdevs);
 }
 
+#[test]
+pub fn loop_test_pool_busy_device() {
+    test_with_spec(DeviceLimits::Range(1, 3), test_pool_busy_device);
+}
+
 #[test]
 pub fn loop_test_force_flag_dirty() {
     test_with_spec(DeviceLimits::Range(1, 3), test_force_flag_dirty);
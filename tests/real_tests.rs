@@ -26,6 +26,7 @@ use util::blockdev_tests::test_blockdevmgr_used;
 use util::blockdev_tests::test_force_flag_dirty;
 use util::blockdev_tests::test_force_flag_stratis;
 use util::blockdev_tests::test_pool_blockdevs;
+use util::blockdev_tests::test_pool_busy_device;
 use util::dm_tests::test_thinpool_device;
 use util::dm_tests::test_linear_device;
 use util::filesystem_tests::test_xfs_expand;
@@ -177,6 +178,11 @@ pub fn real_test_pool_blockdevs() {
     test_with_spec(DeviceLimits::AtLeast(1), test_pool_blockdevs);
 }
 
+#[test]
+pub fn real_test_pool_busy_device() {
+    test_with_spec(DeviceLimits::AtLeast(1), test_pool_busy_device);
+}
+
 #[test]
 pub fn real_test_force_flag_dirty() {
     test_with_spec(DeviceLimits::AtLeast(1), test_force_flag_dirty);
@@ -17,9 +17,9 @@ use self::uuid::Uuid;
 
 use self::devicemapper::{SECTOR_SIZE, Sectors};
 
-use libstratis::engine::Engine;
+use libstratis::engine::{Engine, EngineError, ErrorEnum, OpenMode};
 use libstratis::engine::strat_engine::blockdevmgr::{BlockDevMgr, initialize};
-use libstratis::engine::strat_engine::device::{resolve_devices, write_sectors};
+use libstratis::engine::strat_engine::device::{open_blockdev, resolve_devices, write_sectors};
 use libstratis::engine::strat_engine::engine::DevOwnership;
 use libstratis::engine::strat_engine::metadata::{StaticHeader, BDA_STATIC_HDR_SECTORS,
                                                  MIN_MDA_SECTORS};
@@ -48,7 +48,12 @@ pub fn test_force_flag_dirty(paths: &[&Path]) -> () {
     let unique_devices = resolve_devices(&paths).unwrap();
 
     let uuid = Uuid::new_v4();
-    assert!(initialize(&uuid, unique_devices.clone(), MIN_MDA_SECTORS, false).is_err());
+    assert!(initialize(&uuid,
+                       unique_devices.clone(),
+                       MIN_MDA_SECTORS,
+                       false,
+                       OpenMode::Shared)
+                    .is_err());
     assert!(paths
                 .iter()
                 .enumerate()
@@ -62,7 +67,12 @@ pub fn test_force_flag_dirty(paths: &[&Path]) -> () {
         }
     }));
 
-    assert!(initialize(&uuid, unique_devices.clone(), MIN_MDA_SECTORS, true).is_ok());
+    assert!(initialize(&uuid,
+                       unique_devices.clone(),
+                       MIN_MDA_SECTORS,
+                       true,
+                       OpenMode::Shared)
+                    .is_ok());
     assert!(paths
                 .iter()
                 .all(|path| {
@@ -88,14 +98,34 @@ pub fn test_force_flag_stratis(paths: &[&Path]) -> () {
     let uuid = Uuid::new_v4();
     let uuid2 = Uuid::new_v4();
 
-    initialize(&uuid, unique_devices.clone(), MIN_MDA_SECTORS, false).unwrap();
-    assert!(initialize(&uuid2, unique_devices.clone(), MIN_MDA_SECTORS, false).is_err());
-
-    assert!(initialize(&uuid, unique_devices.clone(), MIN_MDA_SECTORS, false).is_ok());
+    initialize(&uuid,
+               unique_devices.clone(),
+               MIN_MDA_SECTORS,
+               false,
+               OpenMode::Shared)
+            .unwrap();
+    assert!(initialize(&uuid2,
+                       unique_devices.clone(),
+                       MIN_MDA_SECTORS,
+                       false,
+                       OpenMode::Shared)
+                    .is_err());
+
+    assert!(initialize(&uuid,
+                       unique_devices.clone(),
+                       MIN_MDA_SECTORS,
+                       false,
+                       OpenMode::Shared)
+                    .is_ok());
 
     // FIXME: this should succeed, but currently it fails, to be extra safe.
     // See: https://github.com/stratis-storage/stratisd/pull/292
-    assert!(initialize(&uuid2, unique_devices.clone(), MIN_MDA_SECTORS, true).is_err());
+    assert!(initialize(&uuid2,
+                       unique_devices.clone(),
+                       MIN_MDA_SECTORS,
+                       true,
+                       OpenMode::Shared)
+                    .is_err());
 }
 
 
@@ -104,7 +134,7 @@ pub fn test_force_flag_stratis(paths: &[&Path]) -> () {
 pub fn test_pool_blockdevs(paths: &[&Path]) -> () {
     let mut engine = StratEngine::initialize().unwrap();
     let (uuid, blockdevs) = engine
-        .create_pool("test_pool", paths, None, true)
+        .create_pool("test_pool", paths, None, true, false, false)
         .unwrap();
     assert!(blockdevs
                 .iter()
@@ -127,12 +157,24 @@ pub fn test_pool_blockdevs(paths: &[&Path]) -> () {
                      }));
 }
 
+/// Verify that a pool can not be created while one of its blockdevs is held
+/// open exclusively by someone else.
+pub fn test_pool_busy_device(paths: &[&Path]) -> () {
+    let _held = open_blockdev(paths[0], OpenMode::Exclusive).unwrap();
+    let mut engine = StratEngine::initialize().unwrap();
+    assert!(match engine.create_pool("test_pool", paths, None, true, false, false) {
+                Err(EngineError::Engine(ErrorEnum::Busy, _)) => true,
+                _ => false,
+            });
+}
+
 /// Verify that initially, current_capacity() - metadata_size() = avail_space()
 /// After 2 Sectors have been allocated, that amount must also be included in
 /// balance.
 pub fn test_blockdevmgr_used(paths: &[&Path]) -> () {
     let uuid = Uuid::new_v4();
-    let mut mgr = BlockDevMgr::initialize(&uuid, paths, MIN_MDA_SECTORS, false).unwrap();
+    let mut mgr = BlockDevMgr::initialize(&uuid, paths, MIN_MDA_SECTORS, false, OpenMode::Shared)
+        .unwrap();
     assert_eq!(mgr.avail_space() + mgr.metadata_size(),
                mgr.current_capacity());
 
@@ -22,6 +22,7 @@ use self::uuid::Uuid;
 use self::devicemapper::{Bytes, DM, DataBlocks, DmDevice, DmName, IEC, LinearDev, Sectors,
                          Segment, ThinDev, ThinDevId, ThinPoolDev};
 
+use libstratis::engine::OpenMode;
 use libstratis::engine::strat_engine::blockdevmgr::{BlockDevMgr, initialize, map_to_dm};
 use libstratis::engine::strat_engine::device::{blkdev_size, resolve_devices, wipe_sectors};
 use libstratis::engine::strat_engine::metadata::MIN_MDA_SECTORS;
@@ -34,7 +35,8 @@ pub fn test_linear_device(paths: &[&Path]) -> () {
     let initialized = initialize(&Uuid::new_v4(),
                                  unique_devices.clone(),
                                  MIN_MDA_SECTORS,
-                                 false)
+                                 false,
+                                 OpenMode::Shared)
             .unwrap();
     let total_blockdev_size: Sectors = initialized.iter().map(|i| i.avail_range().1).sum();
 
@@ -68,7 +70,8 @@ pub fn test_thinpool_device(paths: &[&Path]) -> () {
     let initialized = initialize(&Uuid::new_v4(),
                                  resolve_devices(&paths).unwrap(),
                                  MIN_MDA_SECTORS,
-                                 false)
+                                 false,
+                                 OpenMode::Shared)
             .unwrap();
 
     let mut bd_mgr = BlockDevMgr::new(initialized);
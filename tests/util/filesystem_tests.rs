@@ -23,7 +23,7 @@ use libstratis::engine::Pool;
 use libstratis::engine::Filesystem;
 use libstratis::engine::strat_engine::filesystem::{FILESYSTEM_LOWATER, fs_usage};
 use libstratis::engine::strat_engine::pool::StratPool;
-use libstratis::engine::types::Redundancy;
+use libstratis::engine::types::{OpenMode, Redundancy};
 
 /// Verify that the logical space allocated to a filesystem is expanded when
 /// the number of sectors written to the filesystem causes the free space to
@@ -37,8 +37,15 @@ pub fn test_xfs_expand(paths: &[&Path]) -> () {
     let fs_size = FILESYSTEM_LOWATER + Bytes(IEC::Mi).sectors();
 
     let (mut pool, _) =
-        StratPool::initialize("stratis_test_pool", &dm, paths, Redundancy::NONE, true).unwrap();
-    let &(_, fs_uuid) = pool.create_filesystems(&[("stratis_test_filesystem", Some(fs_size))])
+        StratPool::initialize("stratis_test_pool",
+                              &dm,
+                              paths,
+                              Redundancy::NONE,
+                              true,
+                              OpenMode::Exclusive)
+                .unwrap();
+    let &(_, fs_uuid) = pool.create_filesystems(&[("stratis_test_filesystem", Some(fs_size))],
+                                                false)
         .unwrap()
         .first()
         .unwrap();
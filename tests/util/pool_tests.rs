@@ -25,7 +25,7 @@ use libstratis::engine::strat_engine::StratEngine;
 use libstratis::engine::strat_engine::device::resolve_devices;
 use libstratis::engine::strat_engine::pool::{DATA_BLOCK_SIZE, DATA_LOWATER, INITIAL_DATA_SIZE,
                                              StratPool};
-use libstratis::engine::types::{Redundancy, RenameAction};
+use libstratis::engine::types::{OpenMode, Redundancy, RenameAction};
 
 /// Verify that the physical space allocated to a pool is expanded when
 /// the number of sectors written to a thin-dev in the pool exceeds the
@@ -36,10 +36,11 @@ pub fn test_thinpool_expand(paths: &[&Path]) -> () {
                                               &DM::new().unwrap(),
                                               paths,
                                               Redundancy::NONE,
-                                              true)
+                                              true,
+                                              OpenMode::Exclusive)
             .unwrap();
 
-    let &(_, fs_uuid) = pool.create_filesystems(&[("stratis_test_filesystem", None)])
+    let &(_, fs_uuid) = pool.create_filesystems(&[("stratis_test_filesystem", None)], false)
         .unwrap()
         .first()
         .unwrap();
@@ -70,8 +71,14 @@ pub fn test_thinpool_expand(paths: &[&Path]) -> () {
 pub fn test_filesystem_snapshot(paths: &[&Path]) {
     let dm = DM::new().unwrap();
     let (mut pool, _) =
-        StratPool::initialize("stratis_test_pool", &dm, paths, Redundancy::NONE, true).unwrap();
-    let &(_, fs_uuid) = pool.create_filesystems(&[("stratis_test_filesystem", None)])
+        StratPool::initialize("stratis_test_pool",
+                              &dm,
+                              paths,
+                              Redundancy::NONE,
+                              true,
+                              OpenMode::Exclusive)
+                .unwrap();
+    let &(_, fs_uuid) = pool.create_filesystems(&[("stratis_test_filesystem", None)], false)
         .unwrap()
         .first()
         .unwrap();
@@ -147,10 +154,10 @@ pub fn test_filesystem_rename(paths: &[&Path]) {
 
     let name1 = "name1";
     let name2 = "name2";
-    let (uuid1, _) = engine.create_pool(&name1, paths, None, false).unwrap();
+    let (uuid1, _) = engine.create_pool(&name1, paths, None, false, false, false).unwrap();
     let fs_uuid = {
         let pool = engine.get_mut_pool(&uuid1).unwrap();
-        let &(fs_name, fs_uuid) = pool.create_filesystems(&[(name1, None)])
+        let &(fs_name, fs_uuid) = pool.create_filesystems(&[(name1, None)], false)
             .unwrap()
             .first()
             .unwrap();
@@ -184,9 +191,10 @@ pub fn test_thinpool_thindev_destroy(paths: &[&Path]) -> () {
                                               &DM::new().unwrap(),
                                               paths,
                                               Redundancy::NONE,
-                                              true)
+                                              true,
+                                              OpenMode::Exclusive)
             .unwrap();
-    let &(_, fs_uuid) = pool.create_filesystems(&[("stratis_test_filesystem", None)])
+    let &(_, fs_uuid) = pool.create_filesystems(&[("stratis_test_filesystem", None)], false)
         .unwrap()
         .first()
         .unwrap();
@@ -24,7 +24,7 @@ use self::devicemapper::{Device, DM};
 
 use libstratis::engine::{Engine, Pool};
 use libstratis::engine::engine::HasUuid;
-use libstratis::engine::types::{Redundancy, RenameAction};
+use libstratis::engine::types::{OpenMode, Redundancy, RenameAction};
 use libstratis::engine::strat_engine::blockdevmgr::initialize;
 use libstratis::engine::strat_engine::device::resolve_devices;
 use libstratis::engine::strat_engine::metadata::MIN_MDA_SECTORS;
@@ -51,7 +51,12 @@ pub fn test_initialize(paths: &[&Path]) -> () {
 
     let unique_devices = resolve_devices(paths1).unwrap();
     let uuid1 = Uuid::new_v4();
-    initialize(&uuid1, unique_devices, MIN_MDA_SECTORS, false).unwrap();
+    initialize(&uuid1,
+               unique_devices,
+               MIN_MDA_SECTORS,
+               false,
+               OpenMode::Shared)
+            .unwrap();
 
     let pools = find_all().unwrap();
     assert!(pools.len() == 1);
@@ -61,7 +66,12 @@ pub fn test_initialize(paths: &[&Path]) -> () {
 
     let unique_devices = resolve_devices(paths2).unwrap();
     let uuid2 = Uuid::new_v4();
-    initialize(&uuid2, unique_devices, MIN_MDA_SECTORS, false).unwrap();
+    initialize(&uuid2,
+               unique_devices,
+               MIN_MDA_SECTORS,
+               false,
+               OpenMode::Shared)
+            .unwrap();
 
     let pools = find_all().unwrap();
     assert!(pools.len() == 2);
@@ -94,11 +104,11 @@ pub fn test_basic_metadata(paths: &[&Path]) {
     let mut engine = StratEngine::initialize().unwrap();
 
     let name1 = "name1";
-    let (uuid1, _) = engine.create_pool(&name1, paths1, None, false).unwrap();
+    let (uuid1, _) = engine.create_pool(&name1, paths1, None, false, false, false).unwrap();
     let metadata1 = engine.get_strat_pool(&uuid1).unwrap().record();
 
     let name2 = "name2";
-    let (uuid2, _) = engine.create_pool(&name2, paths2, None, false).unwrap();
+    let (uuid2, _) = engine.create_pool(&name2, paths2, None, false, false, false).unwrap();
     let metadata2 = engine.get_strat_pool(&uuid2).unwrap().record();
 
     let pools = find_all().unwrap();
@@ -144,10 +154,10 @@ pub fn test_setup(paths: &[&Path]) {
     let mut engine = StratEngine::initialize().unwrap();
 
     let name1 = "name1";
-    let (uuid1, _) = engine.create_pool(&name1, paths1, None, false).unwrap();
+    let (uuid1, _) = engine.create_pool(&name1, paths1, None, false, false, false).unwrap();
 
     let name2 = "name2";
-    let (uuid2, _) = engine.create_pool(&name2, paths2, None, false).unwrap();
+    let (uuid2, _) = engine.create_pool(&name2, paths2, None, false, false, false).unwrap();
 
     assert!(engine.get_pool(&uuid1).is_some());
     assert!(engine.get_pool(&uuid2).is_some());
@@ -167,7 +177,7 @@ pub fn test_pool_rename(paths: &[&Path]) {
     let mut engine = StratEngine::initialize().unwrap();
 
     let name1 = "name1";
-    let (uuid1, _) = engine.create_pool(&name1, paths, None, false).unwrap();
+    let (uuid1, _) = engine.create_pool(&name1, paths, None, false, false, false).unwrap();
 
     let name2 = "name2";
     let action = engine.rename_pool(&uuid1, name2).unwrap();
@@ -187,9 +197,11 @@ pub fn test_pool_rename(paths: &[&Path]) {
 pub fn test_pool_setup(paths: &[&Path]) {
     let dm = DM::new().unwrap();
 
-    let (mut pool, _) = StratPool::initialize("name", &dm, paths, Redundancy::NONE, false).unwrap();
+    let (mut pool, _) =
+        StratPool::initialize("name", &dm, paths, Redundancy::NONE, false, OpenMode::Exclusive)
+            .unwrap();
 
-    let (_, fs_uuid) = pool.create_filesystems(&[("fsname", None)]).unwrap()[0];
+    let (_, fs_uuid) = pool.create_filesystems(&[("fsname", None)], false).unwrap()[0];
 
     let tmp_dir = TempDir::new("stratis_testing").unwrap();
     let new_file = tmp_dir.path().join("stratis_test.txt");